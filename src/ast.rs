@@ -1,44 +1,75 @@
-use std::collections::HashMap;
 use std::fmt;
 
-use crate::utils::Loc;
+use crate::utils::{Loc, Position};
 
+/// Every module of a program: the one it was parsed from first, followed by
+/// the ones it imports, directly or not.
 #[derive(Debug, PartialEq)]
 pub struct Program {
-    pub modules: Module,
+    pub modules: Vec<Module>,
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Module {
+    /// The `::` separated path the module is imported by, e.g. `ui::avatar`.
+    /// Empty for the root module.
+    pub path: String,
     pub loc: Loc,
     pub statements: Vec<Statement>,
+    pub comments: Vec<Comment>,
 }
 
-impl fmt::Display for Module {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            self.statements
-                .iter()
-                .map(|s| format!("{}", s))
-                .collect::<Vec<String>>()
-                .join("")
-        )
-    }
+/// A `# line` or `#| block |#` comment. Comments are trivia: they are kept
+/// in source order on their `Module` instead of being attached to nodes.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Comment {
+    pub loc: Loc,
+    pub kind: CommentKind,
+    pub text: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum CommentKind {
+    Line,
+    Block,
 }
 
 #[derive(Debug, PartialEq)]
 pub enum Statement {
-    Import,
+    Import(Import),
     Component(ComponentDef),
+    /// A child written outside of any definition. The top-level children of
+    /// a module, in order, are what it renders by default.
+    Markup(Child),
+    Error(ErrorNode),
 }
 
-impl fmt::Display for Statement {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Statement::Import => write!(f, ""),
-            Statement::Component(e) => write!(f, "{}", e.body),
+/// `use ui::avatar::Avatar` makes the component `Avatar` of the module
+/// `ui::avatar` available in the importing module. With `pub use`, it is
+/// also exported from the importing module.
+#[derive(Debug, PartialEq)]
+pub struct Import {
+    pub loc: Loc,
+    pub public: bool,
+    pub path: Vec<Id>,
+    pub name: Id,
+}
+
+impl Import {
+    /// The path of the imported module, e.g. `ui::avatar`.
+    pub fn module_path(&self) -> String {
+        self.path
+            .iter()
+            .map(|id| id.name.as_str())
+            .collect::<Vec<_>>()
+            .join("::")
+    }
+
+    /// Where the module path is written, from its first to its last segment.
+    pub fn path_loc(&self) -> Loc {
+        Loc {
+            start: self.path[0].loc.start.clone(),
+            end: self.path[self.path.len() - 1].loc.end.clone(),
         }
     }
 }
@@ -46,18 +77,15 @@ impl fmt::Display for Statement {
 #[derive(Debug, PartialEq)]
 pub struct ComponentDef {
     pub loc: Loc,
+    /// Whether the definition is written `pub def` and so can be imported
+    /// by other modules.
+    pub public: bool,
     pub id: Id,
     pub attributes: Vec<Id>,
     pub body: Body,
 }
 
-impl fmt::Display for ComponentDef {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.body)
-    }
-}
-
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Id {
     pub loc: Loc,
     pub name: String,
@@ -69,70 +97,116 @@ pub struct Body {
     pub children: Vec<Child>,
 }
 
-impl fmt::Display for Body {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            self.children
-                .iter()
-                .map(|c| format!("{}", c))
-                .collect::<Vec<String>>()
-                .join("")
-        )
-    }
-}
-
 #[derive(Debug, PartialEq)]
 pub enum Child {
     Text(TextNode),
     Element(Element),
     Component(ComponentExpr),
+    /// Trusted markup written as `raw "<b>...</b>"`, emitted unescaped.
+    Raw(TextNode),
+    /// Where a component definition renders what its caller passed in.
+    Slot(Slot),
+    For(For),
+    If(If),
+    /// A variable rendered as text, as in `li name`.
+    Expr(Expr),
+    Error(ErrorNode),
 }
 
-impl fmt::Display for Child {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Child {
+    pub fn loc(&self) -> &Loc {
         match self {
-            Child::Text(text) => write!(f, "{}", text.value),
-            Child::Element(element) => write!(f, "{}", element),
-            Child::Component(component) => {
-                write!(f, "{}", component)
-            }
+            Child::Text(text) => &text.loc,
+            Child::Element(element) => &element.loc,
+            Child::Component(component) => &component.loc,
+            Child::Raw(text) => &text.loc,
+            Child::Slot(slot) => &slot.loc,
+            Child::For(for_) => &for_.loc,
+            Child::If(if_) => &if_.loc,
+            Child::Expr(expr) => &expr.loc,
+            Child::Error(error) => &error.loc,
+        }
+    }
+
+    /// The bodies nested directly in the child, in source order.
+    pub fn bodies(&self) -> Vec<&Body> {
+        match self {
+            Child::Element(element) => vec![&element.body],
+            Child::Component(component) => component.body.iter().collect(),
+            Child::Slot(slot) => slot.body.iter().collect(),
+            Child::For(for_) => std::iter::once(&for_.body).chain(&for_.else_body).collect(),
+            Child::If(if_) => if_
+                .branches
+                .iter()
+                .map(|branch| &branch.body)
+                .chain(&if_.else_body)
+                .collect(),
+            Child::Text(_) | Child::Raw(_) | Child::Expr(_) | Child::Error(_) => Vec::new(),
         }
     }
 }
 
+/// `children` (when `name` is `None`) or `slot name`, optionally followed by
+/// a body rendered when the caller leaves the slot empty. At the top of a
+/// call site body, `slot name { ... }` fills the named slot instead.
 #[derive(Debug, PartialEq)]
-pub struct Element {
+pub struct Slot {
     pub loc: Loc,
-    pub tag: Tag,
-    pub attributes: Option<Attributes>,
+    pub name: Option<Id>,
+    pub body: Option<Body>,
+}
+
+/// `for item in items { ... }`, or `for item, i in items { ... }` to also
+/// bind the index of each item. The `else` body is rendered when the list is
+/// empty.
+#[derive(Debug, PartialEq)]
+pub struct For {
+    pub loc: Loc,
+    pub binding: Id,
+    pub index: Option<Id>,
+    pub iterable: Expr,
     pub body: Body,
+    pub else_body: Option<Body>,
 }
 
-impl fmt::Display for Element {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let attr = if let Some(attr) = &self.attributes {
-            format!(" {}", attr)
-        } else {
-            String::new()
-        };
+/// `if a { ... } else if b { ... } else { ... }`: the body of the first
+/// branch whose condition is truthy is rendered, or else `else_body`.
+#[derive(Debug, PartialEq)]
+pub struct If {
+    pub loc: Loc,
+    pub branches: Vec<Branch>,
+    pub else_body: Option<Body>,
+}
 
-        let opening_tag = if self.tag == Tag::Html {
-            format!("<!DOCTYPE html><{}{}>", self.tag, attr)
-        } else {
-            format!("<{}{}>", self.tag, attr)
-        };
+#[derive(Debug, PartialEq)]
+pub struct Branch {
+    pub loc: Loc,
+    pub condition: Expr,
+    pub body: Body,
+}
 
-        let content = format!("{}", &self.body);
+#[derive(Debug, PartialEq)]
+pub struct Element {
+    pub loc: Loc,
+    pub tag: Tag,
+    pub attributes: Option<Attributes>,
+    pub body: Body,
+}
 
-        let closing_tag = if !self.tag.is_self_closing() {
-            format!("</{}>", self.tag)
-        } else {
-            String::new()
+impl Element {
+    /// The location of the tag name that opens the element.
+    pub fn tag_loc(&self) -> Loc {
+        let start = self.loc.start.clone();
+        let end = Position {
+            line: start.line,
+            column: start.column + self.tag.to_string().chars().count(),
         };
+        Loc { start, end }
+    }
 
-        write!(f, "{}{}{}", opening_tag, content, closing_tag)
+    /// The first attribute called `name`.
+    pub fn attribute(&self, name: &str) -> Option<&Attribute> {
+        self.attributes.as_ref().and_then(|a| a.get(name))
     }
 }
 
@@ -193,7 +267,7 @@ pub enum Tag {
     Cite,
     Code,
     Data,
-    Dfm,
+    Dfn,
     Em,
     I,
     Kbd,
@@ -267,7 +341,7 @@ pub enum Tag {
     Label,
     Legend,
     Meter,
-    Otgroup,
+    Optgroup,
     Option,
     Output,
     Progress,
@@ -283,28 +357,493 @@ pub enum Tag {
     // Web Components
     Slot,
     Template,
+    Custom(String),
 }
 
 impl Tag {
+    /// Looks up a standard HTML element by its (lowercase) name.
+    pub fn from_name(name: &str) -> Option<Tag> {
+        match name {
+            // Main root
+            "html" => Some(Tag::Html),
+
+            // Document metadata
+            "base" => Some(Tag::Base),
+            "head" => Some(Tag::Head),
+            "link" => Some(Tag::Link),
+            "meta" => Some(Tag::Meta),
+            "style" => Some(Tag::Style),
+            "title" => Some(Tag::Title),
+
+            // Sectioning root
+            "body" => Some(Tag::Body),
+
+            // Content sectioning
+            "address" => Some(Tag::Address),
+            "article" => Some(Tag::Article),
+            "aside" => Some(Tag::Aside),
+            "footer" => Some(Tag::Footer),
+            "header" => Some(Tag::Header),
+            "h1" => Some(Tag::H1),
+            "h2" => Some(Tag::H2),
+            "h3" => Some(Tag::H3),
+            "h4" => Some(Tag::H4),
+            "h5" => Some(Tag::H5),
+            "h6" => Some(Tag::H6),
+            "main" => Some(Tag::Main),
+            "nav" => Some(Tag::Nav),
+            "section" => Some(Tag::Section),
+
+            // Text content
+            "blockquote" => Some(Tag::Blockquote),
+            "dd" => Some(Tag::Dd),
+            "div" => Some(Tag::Div),
+            "dl" => Some(Tag::Dl),
+            "dt" => Some(Tag::Dt),
+            "figcaption" => Some(Tag::Figcaption),
+            "figure" => Some(Tag::Figure),
+            "hr" => Some(Tag::Hr),
+            "li" => Some(Tag::Li),
+            "ol" => Some(Tag::Ol),
+            "p" => Some(Tag::P),
+            "pre" => Some(Tag::Pre),
+            "ul" => Some(Tag::Ul),
+
+            // Inline text semantics
+            "a" => Some(Tag::A),
+            "abbr" => Some(Tag::Abbr),
+            "b" => Some(Tag::B),
+            "bdi" => Some(Tag::Bdi),
+            "bdo" => Some(Tag::Bdo),
+            "br" => Some(Tag::Br),
+            "cite" => Some(Tag::Cite),
+            "code" => Some(Tag::Code),
+            "data" => Some(Tag::Data),
+            "dfn" => Some(Tag::Dfn),
+            "em" => Some(Tag::Em),
+            "i" => Some(Tag::I),
+            "kbd" => Some(Tag::Kbd),
+            "mark" => Some(Tag::Mark),
+            "q" => Some(Tag::Q),
+            "rb" => Some(Tag::Rb),
+            "rp" => Some(Tag::Rp),
+            "rt" => Some(Tag::Rt),
+            "rtc" => Some(Tag::Rtc),
+            "ruby" => Some(Tag::Ruby),
+            "s" => Some(Tag::S),
+            "samp" => Some(Tag::Samp),
+            "small" => Some(Tag::Small),
+            "span" => Some(Tag::Span),
+            "strong" => Some(Tag::Strong),
+            "sub" => Some(Tag::Sub),
+            "sup" => Some(Tag::Sup),
+            "time" => Some(Tag::Time),
+            "u" => Some(Tag::U),
+            "var" => Some(Tag::Var),
+            "wbr" => Some(Tag::Wbr),
+
+            // Image and multimedia
+            "area" => Some(Tag::Area),
+            "audio" => Some(Tag::Audio),
+            "img" => Some(Tag::Img),
+            "map" => Some(Tag::Map),
+            "track" => Some(Tag::Track),
+            "video" => Some(Tag::Video),
+
+            // Embeded content
+            "embed" => Some(Tag::Embed),
+            "iframe" => Some(Tag::Iframe),
+            "object" => Some(Tag::Object),
+            "param" => Some(Tag::Param),
+            "picture" => Some(Tag::Picture),
+            "portal" => Some(Tag::Portal),
+            "source" => Some(Tag::Source),
+
+            // SVG and MathML
+            "svg" => Some(Tag::Svg),
+            "math" => Some(Tag::Math),
+
+            // Scripting
+            "canvas" => Some(Tag::Canvas),
+            "noscript" => Some(Tag::Noscript),
+            "script" => Some(Tag::Script),
+
+            // Demarcating edits
+            "del" => Some(Tag::Del),
+            "ins" => Some(Tag::Ins),
+
+            // Table content
+            "caption" => Some(Tag::Caption),
+            "col" => Some(Tag::Col),
+            "colgroup" => Some(Tag::Colgroup),
+            "table" => Some(Tag::Table),
+            "tbody" => Some(Tag::Tbody),
+            "td" => Some(Tag::Td),
+            "tfoot" => Some(Tag::Tfoot),
+            "th" => Some(Tag::Th),
+            "thead" => Some(Tag::Thead),
+            "tr" => Some(Tag::Tr),
+
+            // Forms
+            "button" => Some(Tag::Button),
+            "datalist" => Some(Tag::Datalist),
+            "fieldset" => Some(Tag::Fieldset),
+            "form" => Some(Tag::Form),
+            "input" => Some(Tag::Input),
+            "label" => Some(Tag::Label),
+            "legend" => Some(Tag::Legend),
+            "meter" => Some(Tag::Meter),
+            "optgroup" => Some(Tag::Optgroup),
+            "option" => Some(Tag::Option),
+            "output" => Some(Tag::Output),
+            "progress" => Some(Tag::Progress),
+            "select" => Some(Tag::Select),
+            "textarea" => Some(Tag::Textarea),
+
+            // Interactive elements
+            "details" => Some(Tag::Details),
+            "dialog" => Some(Tag::Dialog),
+            "menu" => Some(Tag::Menu),
+            "summary" => Some(Tag::Summary),
+
+            // Web Components
+            "slot" => Some(Tag::Slot),
+            "template" => Some(Tag::Template),
+
+            _ => None,
+        }
+    }
+
     pub fn is_self_closing(&self) -> bool {
+        matches!(
+            self,
+            Tag::Area
+                | Tag::Base
+                | Tag::Br
+                | Tag::Col
+                | Tag::Embed
+                | Tag::Hr
+                | Tag::Img
+                | Tag::Input
+                | Tag::Link
+                | Tag::Meta
+                | Tag::Param
+                | Tag::Source
+                | Tag::Track
+                | Tag::Wbr
+        )
+    }
+}
+
+impl Tag {
+    /// Elements whose text content is not parsed as HTML.
+    pub fn is_raw_text(&self) -> bool {
+        matches!(self, Tag::Script | Tag::Style)
+    }
+
+    /// The level of `h1` to `h6`.
+    pub fn heading_level(&self) -> Option<u8> {
+        match self {
+            Tag::H1 => Some(1),
+            Tag::H2 => Some(2),
+            Tag::H3 => Some(3),
+            Tag::H4 => Some(4),
+            Tag::H5 => Some(5),
+            Tag::H6 => Some(6),
+            _ => None,
+        }
+    }
+}
+
+/// What an element may contain, after the content models of the HTML
+/// standard.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Content {
+    /// Void elements, which have no children at all.
+    Nothing,
+    /// Text only, like `title` or `textarea`.
+    Text,
+    /// The document metadata of `head`.
+    Metadata,
+    /// Text and inline elements, like the content of `p`.
+    Phrasing,
+    /// Anything that can go in `body`.
+    Flow,
+    /// Whatever the parent of the element may contain, like `a` or `ins`.
+    Transparent,
+    /// Only the elements listed by [`Tag::allows`], like `li` in `ul`.
+    Specific,
+    /// Content Hyper does not check: foreign, custom or template content.
+    Any,
+}
+
+impl Content {
+    /// Whether an element of this category may hold `child`.
+    pub fn allows(self, child: &Tag) -> bool {
+        match self {
+            Content::Nothing | Content::Text | Content::Specific => false,
+            Content::Metadata => child.is_metadata(),
+            Content::Phrasing => child.is_phrasing(),
+            Content::Flow => child.is_flow(),
+            Content::Transparent | Content::Any => true,
+        }
+    }
+}
+
+impl Tag {
+    pub fn content(&self) -> Content {
         match self {
-            Tag::Area => true,
-            Tag::Base => true,
-            Tag::Br => true,
-            Tag::Col => true,
-            Tag::Embed => true,
-            Tag::Hr => true,
-            Tag::Img => true,
-            Tag::Input => true,
-            Tag::Link => true,
-            Tag::Meta => true,
-            Tag::Param => true,
-            Tag::Source => true,
-            Tag::Track => true,
-            Tag::Wbr => true,
+            tag if tag.is_self_closing() => Content::Nothing,
+
+            Tag::Title | Tag::Textarea | Tag::Script | Tag::Style | Tag::Option | Tag::Rp => {
+                Content::Text
+            }
+
+            Tag::Head => Content::Metadata,
+
+            Tag::P
+            | Tag::H1
+            | Tag::H2
+            | Tag::H3
+            | Tag::H4
+            | Tag::H5
+            | Tag::H6
+            | Tag::Pre
+            | Tag::Abbr
+            | Tag::B
+            | Tag::Bdi
+            | Tag::Bdo
+            | Tag::Cite
+            | Tag::Code
+            | Tag::Data
+            | Tag::Dfn
+            | Tag::Em
+            | Tag::I
+            | Tag::Kbd
+            | Tag::Mark
+            | Tag::Q
+            | Tag::Rb
+            | Tag::Rt
+            | Tag::Rtc
+            | Tag::Ruby
+            | Tag::S
+            | Tag::Samp
+            | Tag::Small
+            | Tag::Span
+            | Tag::Strong
+            | Tag::Sub
+            | Tag::Sup
+            | Tag::Time
+            | Tag::U
+            | Tag::Var
+            | Tag::Button
+            | Tag::Datalist
+            | Tag::Label
+            | Tag::Legend
+            | Tag::Meter
+            | Tag::Output
+            | Tag::Progress
+            | Tag::Summary => Content::Phrasing,
+
+            Tag::Body
+            | Tag::Address
+            | Tag::Article
+            | Tag::Aside
+            | Tag::Footer
+            | Tag::Header
+            | Tag::Main
+            | Tag::Nav
+            | Tag::Section
+            | Tag::Blockquote
+            | Tag::Dd
+            | Tag::Div
+            | Tag::Dt
+            | Tag::Figcaption
+            | Tag::Figure
+            | Tag::Li
+            | Tag::Caption
+            | Tag::Td
+            | Tag::Th
+            | Tag::Fieldset
+            | Tag::Form
+            | Tag::Details
+            | Tag::Dialog => Content::Flow,
+
+            Tag::A
+            | Tag::Audio
+            | Tag::Map
+            | Tag::Video
+            | Tag::Object
+            | Tag::Canvas
+            | Tag::Del
+            | Tag::Ins
+            | Tag::Slot => Content::Transparent,
+
+            Tag::Html
+            | Tag::Dl
+            | Tag::Ol
+            | Tag::Ul
+            | Tag::Menu
+            | Tag::Picture
+            | Tag::Colgroup
+            | Tag::Table
+            | Tag::Tbody
+            | Tag::Tfoot
+            | Tag::Thead
+            | Tag::Tr
+            | Tag::Select
+            | Tag::Optgroup => Content::Specific,
+
+            _ => Content::Any,
+        }
+    }
+
+    /// Whether this element may hold `child` besides what its content
+    /// category allows, like `li` in `ul` or `legend` in `fieldset`.
+    pub fn allows(&self, child: &Tag) -> bool {
+        match (self, child) {
+            (_, Tag::Script | Tag::Template) => !matches!(
+                self.content(),
+                Content::Nothing | Content::Text | Content::Metadata
+            ),
+            (Tag::Html, Tag::Head | Tag::Body) => true,
+            (Tag::Dl, Tag::Dt | Tag::Dd | Tag::Div) => true,
+            (Tag::Ol | Tag::Ul | Tag::Menu, Tag::Li) => true,
+            (Tag::Picture, Tag::Source | Tag::Img) => true,
+            (Tag::Audio | Tag::Video, Tag::Source | Tag::Track) => true,
+            (Tag::Colgroup, Tag::Col) => true,
+            (
+                Tag::Table,
+                Tag::Caption | Tag::Colgroup | Tag::Thead | Tag::Tbody | Tag::Tfoot | Tag::Tr,
+            ) => true,
+            (Tag::Thead | Tag::Tbody | Tag::Tfoot, Tag::Tr) => true,
+            (Tag::Tr, Tag::Td | Tag::Th) => true,
+            (Tag::Select, Tag::Option | Tag::Optgroup | Tag::Hr) => true,
+            (Tag::Optgroup | Tag::Datalist, Tag::Option) => true,
+            (Tag::Figure, Tag::Figcaption) => true,
+            (Tag::Fieldset, Tag::Legend) => true,
+            (Tag::Details, Tag::Summary) => true,
+            (Tag::Ruby, Tag::Rb | Tag::Rp | Tag::Rt | Tag::Rtc) => true,
+            (Tag::Rtc, Tag::Rp | Tag::Rt) => true,
             _ => false,
         }
     }
+
+    /// Elements that set up the document rather than show content. Most of
+    /// them only belong in `head`.
+    pub fn is_metadata(&self) -> bool {
+        matches!(
+            self,
+            Tag::Base
+                | Tag::Link
+                | Tag::Meta
+                | Tag::Noscript
+                | Tag::Script
+                | Tag::Style
+                | Tag::Template
+                | Tag::Title
+        )
+    }
+
+    pub fn is_flow(&self) -> bool {
+        !matches!(
+            self,
+            Tag::Html
+                | Tag::Head
+                | Tag::Body
+                | Tag::Base
+                | Tag::Link
+                | Tag::Meta
+                | Tag::Style
+                | Tag::Title
+                | Tag::Dd
+                | Tag::Dt
+                | Tag::Li
+                | Tag::Figcaption
+                | Tag::Legend
+                | Tag::Summary
+                | Tag::Caption
+                | Tag::Col
+                | Tag::Colgroup
+                | Tag::Tbody
+                | Tag::Td
+                | Tag::Tfoot
+                | Tag::Th
+                | Tag::Thead
+                | Tag::Tr
+                | Tag::Optgroup
+                | Tag::Option
+                | Tag::Param
+                | Tag::Source
+                | Tag::Track
+                | Tag::Rb
+                | Tag::Rp
+                | Tag::Rt
+                | Tag::Rtc
+        )
+    }
+
+    pub fn is_phrasing(&self) -> bool {
+        matches!(
+            self,
+            Tag::A
+                | Tag::Abbr
+                | Tag::Area
+                | Tag::Audio
+                | Tag::B
+                | Tag::Bdi
+                | Tag::Bdo
+                | Tag::Br
+                | Tag::Button
+                | Tag::Canvas
+                | Tag::Cite
+                | Tag::Code
+                | Tag::Data
+                | Tag::Datalist
+                | Tag::Del
+                | Tag::Dfn
+                | Tag::Em
+                | Tag::Embed
+                | Tag::I
+                | Tag::Iframe
+                | Tag::Img
+                | Tag::Input
+                | Tag::Ins
+                | Tag::Kbd
+                | Tag::Label
+                | Tag::Map
+                | Tag::Mark
+                | Tag::Math
+                | Tag::Meter
+                | Tag::Noscript
+                | Tag::Object
+                | Tag::Output
+                | Tag::Picture
+                | Tag::Portal
+                | Tag::Progress
+                | Tag::Q
+                | Tag::Ruby
+                | Tag::S
+                | Tag::Samp
+                | Tag::Script
+                | Tag::Select
+                | Tag::Slot
+                | Tag::Small
+                | Tag::Span
+                | Tag::Strong
+                | Tag::Sub
+                | Tag::Sup
+                | Tag::Svg
+                | Tag::Template
+                | Tag::Textarea
+                | Tag::Time
+                | Tag::U
+                | Tag::Var
+                | Tag::Video
+                | Tag::Wbr
+                | Tag::Custom(_)
+        )
+    }
 }
 
 impl fmt::Display for Tag {
@@ -345,18 +884,18 @@ impl fmt::Display for Tag {
             Tag::P => write!(f, "p"),
             Tag::Pre => write!(f, "pre"),
             Tag::Ul => write!(f, "ul"),
-            Tag::A => write!(f, "q"),
+            Tag::A => write!(f, "a"),
             Tag::Abbr => write!(f, "abbr"),
-            Tag::B => write!(f, "B"),
+            Tag::B => write!(f, "b"),
             Tag::Bdi => write!(f, "bdi"),
             Tag::Bdo => write!(f, "bdo"),
             Tag::Br => write!(f, "br"),
             Tag::Cite => write!(f, "cite"),
             Tag::Code => write!(f, "code"),
             Tag::Data => write!(f, "data"),
-            Tag::Dfm => write!(f, "dfm"),
+            Tag::Dfn => write!(f, "dfn"),
             Tag::Em => write!(f, "em"),
-            Tag::I => write!(f, "I"),
+            Tag::I => write!(f, "i"),
             Tag::Kbd => write!(f, "kbd"),
             Tag::Mark => write!(f, "mark"),
             Tag::Q => write!(f, "q"),
@@ -365,7 +904,7 @@ impl fmt::Display for Tag {
             Tag::Rt => write!(f, "rt"),
             Tag::Rtc => write!(f, "rtc"),
             Tag::Ruby => write!(f, "ruby"),
-            Tag::S => write!(f, "S"),
+            Tag::S => write!(f, "s"),
             Tag::Samp => write!(f, "samp"),
             Tag::Small => write!(f, "small"),
             Tag::Span => write!(f, "span"),
@@ -373,7 +912,7 @@ impl fmt::Display for Tag {
             Tag::Sub => write!(f, "sub"),
             Tag::Sup => write!(f, "sup"),
             Tag::Time => write!(f, "time"),
-            Tag::U => write!(f, "U"),
+            Tag::U => write!(f, "u"),
             Tag::Var => write!(f, "var"),
             Tag::Wbr => write!(f, "wbr"),
             Tag::Area => write!(f, "area"),
@@ -414,7 +953,7 @@ impl fmt::Display for Tag {
             Tag::Label => write!(f, "label"),
             Tag::Legend => write!(f, "legend"),
             Tag::Meter => write!(f, "meter"),
-            Tag::Otgroup => write!(f, "otgroup"),
+            Tag::Optgroup => write!(f, "optgroup"),
             Tag::Option => write!(f, "option"),
             Tag::Output => write!(f, "output"),
             Tag::Progress => write!(f, "progress"),
@@ -426,6 +965,7 @@ impl fmt::Display for Tag {
             Tag::Summary => write!(f, "summary"),
             Tag::Slot => write!(f, "slot"),
             Tag::Template => write!(f, "template"),
+            Tag::Custom(name) => write!(f, "{}", name),
         }
     }
 }
@@ -438,40 +978,109 @@ pub struct ComponentExpr {
     pub body: Option<Body>,
 }
 
-impl fmt::Display for ComponentExpr {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            self.body
-                .as_ref()
-                .map_or_else(|| String::default(), |b| format!("{}", b))
-        )
+#[derive(Debug, PartialEq)]
+pub struct Attributes {
+    pub loc: Loc,
+    pub attr: Vec<Attribute>,
+}
+
+impl Attributes {
+    /// The first attribute called `name`.
+    pub fn get(&self, name: &str) -> Option<&Attribute> {
+        self.attr.iter().find(|a| a.name.name == name)
     }
 }
 
+/// A single `name=value` or `name` attribute, kept in source order.
 #[derive(Debug, PartialEq)]
-pub struct Attributes {
+pub struct Attribute {
     pub loc: Loc,
-    pub attr: HashMap<String, String>,
+    pub name: Id,
+    pub value: Option<Expr>,
 }
 
-impl fmt::Display for Attributes {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            self.attr
-                .iter()
-                .map(|(k, v)| format!("{}=\"{}\"", k, v))
-                .collect::<Vec<String>>()
-                .join(" ")
-        )
+impl Attribute {
+    /// The value of the attribute when it is a plain string literal.
+    pub fn literal(&self) -> Option<&str> {
+        match self.value.as_ref().map(|value| &value.kind) {
+            Some(ExprKind::Str(segments)) => match segments.as_slice() {
+                [] => Some(""),
+                [Segment::Text(text)] => Some(text),
+                _ => None,
+            },
+            _ => None,
+        }
     }
 }
 
 #[derive(Debug, PartialEq)]
 pub struct TextNode {
     pub loc: Loc,
-    pub value: String,
+    pub segments: Vec<Segment>,
+}
+
+/// A piece of a string literal: plain text, or an expression interpolated
+/// with `{expr}`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Segment {
+    Text(String),
+    Expr(Expr),
+}
+
+/// Stands in for source the parser could not make sense of. The matching
+/// diagnostic is reported separately.
+#[derive(Debug, PartialEq)]
+pub struct ErrorNode {
+    pub loc: Loc,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Expr {
+    pub loc: Loc,
+    pub kind: ExprKind,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ExprKind {
+    /// A string literal, `"..."`, which may interpolate expressions.
+    Str(Vec<Segment>),
+    /// A reference to a component parameter or loop variable, `src`.
+    Var(String),
+    /// A number literal, `42` or `0.5`.
+    Number(f64),
+    /// `true` or `false`.
+    Bool(bool),
+    /// `expr.name`, an entry of a map.
+    Field(Box<Expr>, Id),
+    /// `!expr`
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BinaryOp {
+    And,
+    Or,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+        };
+        write!(f, "{}", op)
+    }
 }
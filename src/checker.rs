@@ -0,0 +1,344 @@
+//! Checks the meaning of a parsed program without rendering it: every
+//! component a module uses must be defined or imported there, and every call
+//! must match the parameters and slots of the component it calls.
+
+use std::collections::HashMap;
+
+use crate::{
+    ast::*,
+    diagnostic::{codes, Diagnostic},
+    loader::{components, Component},
+    utils::Loc,
+};
+
+pub struct Checker<'a> {
+    modules: &'a [Module],
+    symbols: Vec<HashMap<&'a str, Component<'a>>>,
+    /// The module being checked.
+    module: usize,
+    /// The parameters and loop variables in scope, innermost last.
+    scope: Vec<Binding<'a>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+struct Binding<'a> {
+    id: &'a Id,
+    used: bool,
+}
+
+impl<'a> Checker<'a> {
+    /// Checks every module of `program`. Names that are neither parameters
+    /// nor loop variables are left alone: they are read from the render
+    /// context.
+    pub fn check(program: &'a Program) -> Vec<Diagnostic> {
+        let mut checker = Checker {
+            modules: &program.modules,
+            symbols: components(&program.modules),
+            module: 0,
+            scope: Vec::new(),
+            diagnostics: Vec::new(),
+        };
+        for module in 0..program.modules.len() {
+            checker.module = module;
+            checker.duplicates(module);
+        }
+        for module in 0..program.modules.len() {
+            checker.module = module;
+            checker.statements(&program.modules[module].statements);
+        }
+        checker.diagnostics
+    }
+
+    /// Reports the names defined or imported more than once in
+    /// `modules[index]`.
+    fn duplicates(&mut self, index: usize) {
+        let modules = self.modules;
+        let mut seen: HashMap<&str, &Loc> = HashMap::new();
+        for statement in &modules[index].statements {
+            let id = match statement {
+                Statement::Import(import) => &import.name,
+                Statement::Component(def) => &def.id,
+                Statement::Markup(_) | Statement::Error(_) => continue,
+            };
+            match seen.get(id.name.as_str()) {
+                Some(first) => {
+                    let message = format!(
+                        "`{}` is defined more than once, first at {}:{}",
+                        id.name, first.start.line, first.start.column
+                    );
+                    self.report(Diagnostic::error(
+                        codes::DUPLICATE_DEFINITION,
+                        message,
+                        id.loc.clone(),
+                    ));
+                }
+                None => {
+                    seen.insert(&id.name, &id.loc);
+                }
+            }
+        }
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) {
+        let module = &self.modules[self.module];
+        self.diagnostics.push(diagnostic.in_module_of(module));
+    }
+
+    fn statements(&mut self, statements: &'a [Statement]) {
+        for statement in statements {
+            match statement {
+                Statement::Component(def) => self.def(def),
+                Statement::Markup(child) => self.child(child),
+                Statement::Import(_) | Statement::Error(_) => {}
+            }
+        }
+    }
+
+    fn def(&mut self, def: &'a ComponentDef) {
+        self.scope = def
+            .attributes
+            .iter()
+            .map(|id| Binding { id, used: false })
+            .collect();
+        self.body(&def.body);
+
+        for binding in std::mem::take(&mut self.scope) {
+            if !binding.used {
+                self.report(Diagnostic::warning(
+                    codes::UNUSED_PARAMETER,
+                    format!(
+                        "parameter `{}` of `{}` is never used",
+                        binding.id.name, def.id.name
+                    ),
+                    binding.id.loc.clone(),
+                ));
+            }
+        }
+    }
+
+    fn body(&mut self, body: &'a Body) {
+        for child in &body.children {
+            self.child(child);
+        }
+    }
+
+    fn child(&mut self, child: &'a Child) {
+        match child {
+            Child::Text(text) | Child::Raw(text) => self.segments(&text.segments),
+            Child::Element(element) => {
+                if let Some(attributes) = &element.attributes {
+                    self.attributes(attributes);
+                }
+                self.body(&element.body);
+            }
+            Child::Component(component) => self.component(component),
+            Child::Slot(slot) => {
+                if let Some(body) = &slot.body {
+                    self.body(body);
+                }
+            }
+            Child::For(for_) => {
+                self.expr(&for_.iterable);
+                if let Some(else_body) = &for_.else_body {
+                    self.body(else_body);
+                }
+
+                let depth = self.scope.len();
+                for id in std::iter::once(&for_.binding).chain(&for_.index) {
+                    self.bind(id);
+                }
+                self.body(&for_.body);
+                self.scope.truncate(depth);
+            }
+            Child::If(if_) => {
+                for branch in &if_.branches {
+                    self.expr(&branch.condition);
+                    self.body(&branch.body);
+                }
+                if let Some(else_body) = &if_.else_body {
+                    self.body(else_body);
+                }
+            }
+            Child::Expr(expr) => self.expr(expr),
+            Child::Error(_) => {}
+        }
+    }
+
+    /// Brings the loop variable `id` into scope, warning when it hides a
+    /// parameter or an outer loop variable.
+    fn bind(&mut self, id: &'a Id) {
+        if let Some(outer) = self.scope.iter().rev().find(|b| b.id.name == id.name) {
+            let message = format!(
+                "`{}` shadows the variable declared at {}:{}",
+                id.name, outer.id.loc.start.line, outer.id.loc.start.column
+            );
+            self.report(Diagnostic::warning(
+                codes::SHADOWED_VARIABLE,
+                message,
+                id.loc.clone(),
+            ));
+        }
+        self.scope.push(Binding { id, used: false });
+    }
+
+    /// Marks the variable `name` as used, if it is in scope.
+    fn use_variable(&mut self, name: &str) {
+        if let Some(binding) = self.scope.iter_mut().rev().find(|b| b.id.name == name) {
+            binding.used = true;
+        }
+    }
+
+    fn attributes(&mut self, attributes: &'a Attributes) {
+        for attribute in &attributes.attr {
+            match &attribute.value {
+                Some(value) => self.expr(value),
+                None => self.use_variable(&attribute.name.name),
+            }
+        }
+    }
+
+    fn component(&mut self, component: &'a ComponentExpr) {
+        let name = component.id.name.as_str();
+        match self.symbols[self.module].get(name).map(|c| c.def) {
+            Some(def) => {
+                let arguments = check_arguments(def, component);
+                for diagnostic in arguments.into_iter().chain(check_slots(def, component)) {
+                    self.report(diagnostic);
+                }
+            }
+            None => self.report(Diagnostic::error(
+                codes::UNKNOWN_COMPONENT,
+                format!("cannot find component `{}`", name),
+                component.id.loc.clone(),
+            )),
+        }
+
+        if let Some(attributes) = &component.attributes {
+            self.attributes(attributes);
+        }
+        if let Some(body) = &component.body {
+            self.body(body);
+        }
+    }
+
+    fn segments(&mut self, segments: &'a [Segment]) {
+        for segment in segments {
+            if let Segment::Expr(expr) = segment {
+                self.expr(expr);
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &'a Expr) {
+        match &expr.kind {
+            ExprKind::Str(segments) => self.segments(segments),
+            ExprKind::Var(name) => self.use_variable(name),
+            ExprKind::Field(map, _) => self.expr(map),
+            ExprKind::Not(operand) => self.expr(operand),
+            ExprKind::Binary(_, lhs, rhs) => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            ExprKind::Number(_) | ExprKind::Bool(_) => {}
+        }
+    }
+}
+
+/// Checks the attributes passed at `call` against the parameters of `def`:
+/// every attribute must be a parameter, and every parameter must be passed.
+pub(crate) fn check_arguments(def: &ComponentDef, call: &ComponentExpr) -> Vec<Diagnostic> {
+    let name = &call.id.name;
+    let attributes = call.attributes.as_ref();
+    let mut diagnostics = Vec::new();
+    for attribute in attributes.iter().flat_map(|a| &a.attr) {
+        if !def.attributes.iter().any(|p| p.name == attribute.name.name) {
+            diagnostics.push(Diagnostic::error(
+                codes::UNKNOWN_PARAMETER,
+                format!(
+                    "component `{}` has no parameter `{}`",
+                    name, attribute.name.name
+                ),
+                attribute.name.loc.clone(),
+            ));
+        }
+    }
+    for param in &def.attributes {
+        if attributes.and_then(|a| a.get(&param.name)).is_none() {
+            diagnostics.push(Diagnostic::error(
+                codes::MISSING_PARAMETER,
+                format!("component `{}` requires parameter `{}`", name, param.name),
+                call.id.loc.clone(),
+            ));
+        }
+    }
+    diagnostics
+}
+
+/// Checks the content passed at `call` against the slots `def` renders: a
+/// named slot must be one of them, and other children need a `children`.
+pub(crate) fn check_slots(def: &ComponentDef, call: &ComponentExpr) -> Vec<Diagnostic> {
+    let name = &call.id.name;
+    let declared = slots(&def.body);
+    let mut children = false;
+    let mut diagnostics = Vec::new();
+    for child in call.body.iter().flat_map(|body| &body.children) {
+        match child {
+            Child::Slot(Slot {
+                name: Some(slot),
+                body: Some(_),
+                ..
+            }) => {
+                if !declared.contains(&Some(&slot.name)) {
+                    diagnostics.push(Diagnostic::error(
+                        codes::UNKNOWN_SLOT,
+                        format!("component `{}` has no slot `{}`", name, slot.name),
+                        slot.loc.clone(),
+                    ));
+                }
+            }
+            _ => children = true,
+        }
+    }
+    if children && !declared.contains(&None) {
+        diagnostics.push(Diagnostic::error(
+            codes::UNEXPECTED_CHILDREN,
+            format!("component `{}` does not render its children", name),
+            call.id.loc.clone(),
+        ));
+    }
+    diagnostics
+}
+
+/// The names of the slots rendered in `body`, `None` standing for
+/// `children`. Slots in the fallback of another slot count too, but not the
+/// ones passed to another component.
+fn slots(body: &Body) -> Vec<Option<&String>> {
+    let mut names = Vec::new();
+    for child in &body.children {
+        match child {
+            Child::Slot(slot) => {
+                names.push(slot.name.as_ref().map(|id| &id.name));
+                if let Some(fallback) = &slot.body {
+                    names.extend(slots(fallback));
+                }
+            }
+            Child::Element(element) => names.extend(slots(&element.body)),
+            Child::For(for_) => {
+                names.extend(slots(&for_.body));
+                if let Some(else_body) = &for_.else_body {
+                    names.extend(slots(else_body));
+                }
+            }
+            Child::If(if_) => {
+                for branch in &if_.branches {
+                    names.extend(slots(&branch.body));
+                }
+                if let Some(else_body) = &if_.else_body {
+                    names.extend(slots(else_body));
+                }
+            }
+            _ => {}
+        }
+    }
+    names
+}
@@ -1,9 +1,652 @@
-use crate::ast::*;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::{
+    ast::*,
+    checker::{check_arguments, check_slots},
+    diagnostic::{codes, CompileError, Diagnostic},
+    escape,
+    loader::{components, Component},
+    utils::Loc,
+    value::{Context, Props, Value},
+};
 
 pub struct Compiler {}
 
 impl Compiler {
-    pub fn compile(program: Program) -> String {
-        format!("{}", program.modules)
+    /// Compiles the top-level markup of `program`, or its `Main` component
+    /// when there is none.
+    pub fn compile(program: Program) -> Result<String, CompileError> {
+        Renderer::new(&program).render(&Props::new())
+    }
+
+    /// Compiles the component called `entry`, with `props` bound to its
+    /// parameters. The other definitions are only rendered where `entry`
+    /// uses them.
+    pub fn compile_entry(
+        program: Program,
+        entry: &str,
+        props: Props,
+    ) -> Result<String, CompileError> {
+        let renderer = Renderer::new(&program).with_entry(entry);
+        let mut diagnostics = Vec::new();
+        if let Some(Component { def, .. }) = renderer.components[0].get(entry) {
+            for name in props.keys() {
+                if !def.attributes.iter().any(|p| &p.name == name) {
+                    diagnostics.push(Diagnostic::error(
+                        codes::UNKNOWN_PARAMETER,
+                        format!("component `{}` has no parameter `{}`", entry, name),
+                        def.id.loc.clone(),
+                    ));
+                }
+            }
+        }
+
+        match renderer.render(&props) {
+            Ok(html) if diagnostics.is_empty() => Ok(html),
+            Ok(_) => Err(CompileError::new(diagnostics)),
+            Err(error) => {
+                diagnostics.extend(error.diagnostics);
+                Err(CompileError::new(diagnostics))
+            }
+        }
+    }
+}
+
+/// Renders a parsed program many times over, with different data each time.
+///
+/// The parameters of the entry component are looked up in the `Context`
+/// passed to `render`, as is any other name that is not in scope, so that
+/// data such as the current user is available to every component.
+pub struct Renderer<'a> {
+    modules: &'a [Module],
+    components: Vec<HashMap<&'a str, Component<'a>>>,
+    /// The component to render, if not the default output.
+    entry: Option<String>,
+}
+
+impl<'a> Renderer<'a> {
+    /// A renderer for the default output of `program`: the top-level markup
+    /// of its root module, or its `Main` component when there is none.
+    pub fn new(program: &'a Program) -> Self {
+        let modules = program.modules.as_slice();
+        Self {
+            modules,
+            components: components(modules),
+            entry: None,
+        }
+    }
+
+    /// Renders the component called `entry` instead of the default output.
+    pub fn with_entry(mut self, entry: &str) -> Self {
+        self.entry = Some(entry.to_owned());
+        self
+    }
+
+    pub fn render(&self, context: &dyn Context) -> Result<String, CompileError> {
+        let mut codegen = Codegen::new(self.modules, &self.components, context);
+        let markup: Vec<&Child> = self.modules[0]
+            .statements
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::Markup(child) => Some(child),
+                _ => None,
+            })
+            .collect();
+        let entry = match &self.entry {
+            Some(entry) => entry.as_str(),
+            None if !markup.is_empty() => {
+                for child in markup {
+                    codegen.child(child, &Scope::new());
+                }
+                return codegen.finish();
+            }
+            None => "Main",
+        };
+
+        match self.components[0].get(entry).copied() {
+            Some(component) => {
+                let def = component.def;
+                let mut scope = Scope::new();
+                for param in &def.attributes {
+                    let value = context.get(&param.name);
+                    if value.is_none() {
+                        let diagnostic = Diagnostic::error(
+                            codes::MISSING_PARAMETER,
+                            format!("component `{}` requires parameter `{}`", entry, param.name),
+                            def.id.loc.clone(),
+                        );
+                        codegen.report_in(component.module, diagnostic);
+                    }
+                    scope.insert(param.name.as_str(), value);
+                }
+                codegen.instantiate(&scope, Frame::root(component));
+            }
+            None => codegen.report(Diagnostic::error(
+                codes::UNKNOWN_ENTRY,
+                format!("cannot find entry component `{}`", entry),
+                Loc {
+                    start: self.modules[0].loc.start.clone(),
+                    end: self.modules[0].loc.start.clone(),
+                },
+            )),
+        }
+        codegen.finish()
+    }
+}
+
+/// The parameters of the component being rendered. A declared parameter the
+/// caller did not pass is bound to `None`.
+type Scope<'a> = HashMap<&'a str, Option<Value>>;
+
+/// A component being expanded, with the content its caller passed in.
+struct Frame<'a> {
+    component: Component<'a>,
+    /// The call site children that are not filling a named slot.
+    children: Vec<&'a Child>,
+    slots: HashMap<&'a str, &'a Body>,
+    /// The scope the caller's content is rendered in.
+    caller: Scope<'a>,
+}
+
+impl<'a> Frame<'a> {
+    fn root(component: Component<'a>) -> Self {
+        Self {
+            component,
+            children: Vec::new(),
+            slots: HashMap::new(),
+            caller: Scope::new(),
+        }
+    }
+}
+
+struct Codegen<'a> {
+    modules: &'a [Module],
+    components: &'a [HashMap<&'a str, Component<'a>>],
+    context: &'a dyn Context,
+    /// The components being expanded, innermost last.
+    stack: Vec<Frame<'a>>,
+    /// The tag of the raw text element (`script`, `style`) being rendered,
+    /// whose content is not escaped like ordinary text.
+    raw_text: Option<String>,
+    out: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Codegen<'a> {
+    fn new(
+        modules: &'a [Module],
+        components: &'a [HashMap<&'a str, Component<'a>>],
+        context: &'a dyn Context,
+    ) -> Self {
+        Self {
+            modules,
+            components,
+            context,
+            stack: Vec::new(),
+            raw_text: None,
+            out: String::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn finish(self) -> Result<String, CompileError> {
+        if self.diagnostics.is_empty() {
+            Ok(self.out)
+        } else {
+            Err(CompileError::new(self.diagnostics))
+        }
+    }
+
+    /// Reports a diagnostic about the module of the component being rendered.
+    fn report(&mut self, diagnostic: Diagnostic) {
+        let module = self.stack.last().map_or(0, |frame| frame.component.module);
+        self.report_in(module, diagnostic);
+    }
+
+    fn report_in(&mut self, module: usize, diagnostic: Diagnostic) {
+        let diagnostic = diagnostic.in_module_of(&self.modules[module]);
+        if !self.diagnostics.contains(&diagnostic) {
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    fn instantiate(&mut self, scope: &Scope<'a>, frame: Frame<'a>) {
+        let def = frame.component.def;
+        self.stack.push(frame);
+        self.body(&def.body, scope);
+        self.stack.pop();
+    }
+
+    fn body(&mut self, body: &'a Body, scope: &Scope<'a>) {
+        for child in &body.children {
+            self.child(child, scope);
+        }
+    }
+
+    /// Escapes text for the element being rendered: as raw text inside
+    /// `script` and `style`, and as ordinary text anywhere else.
+    fn escaper(&self) -> impl Fn(&str) -> String {
+        let raw_text = self.raw_text.clone();
+        move |value| match &raw_text {
+            Some(tag) => escape::raw_text(value, tag),
+            None => escape::text(value),
+        }
+    }
+
+    fn child(&mut self, child: &'a Child, scope: &Scope<'a>) {
+        match child {
+            Child::Text(text) => {
+                let escape = self.escaper();
+                let text = self.interpolate(&text.segments, scope, &escape, &escape);
+                self.out.push_str(&text);
+            }
+            Child::Raw(text) => {
+                let escape = self.escaper();
+                let text = self.interpolate(&text.segments, scope, str::to_owned, escape);
+                self.out.push_str(&text);
+            }
+            Child::Element(element) => self.element(element, scope),
+            Child::Component(component) => self.component(component, scope),
+            Child::Slot(slot) => self.slot(slot, scope),
+            Child::For(for_) => self.for_loop(for_, scope),
+            Child::If(if_) => {
+                for branch in &if_.branches {
+                    if self.truthy(&branch.condition, scope) == Some(true) {
+                        return self.body(&branch.body, scope);
+                    }
+                }
+                if let Some(else_body) = &if_.else_body {
+                    self.body(else_body, scope);
+                }
+            }
+            Child::Expr(expr) => {
+                if let Some(value) = self.eval(expr, scope) {
+                    let escape = self.escaper();
+                    self.out.push_str(&escape(&value.to_string()));
+                }
+            }
+            Child::Error(_) => {}
+        }
+    }
+
+    /// Renders the body of `for_` once per item of its list, with the item
+    /// (and its index) bound on top of `scope`.
+    fn for_loop(&mut self, for_: &'a For, scope: &Scope<'a>) {
+        let items = match self.eval(&for_.iterable, scope) {
+            Some(Value::List(items)) => items,
+            Some(value) => {
+                return self.report(Diagnostic::error(
+                    codes::NOT_ITERABLE,
+                    format!("expected a list, found a {}", value.type_name()),
+                    for_.iterable.loc.clone(),
+                ))
+            }
+            None => return,
+        };
+
+        if items.is_empty() {
+            if let Some(else_body) = &for_.else_body {
+                self.body(else_body, scope);
+            }
+            return;
+        }
+        let mut inner = scope.clone();
+        for (i, item) in items.into_iter().enumerate() {
+            inner.insert(&for_.binding.name, Some(item));
+            if let Some(index) = &for_.index {
+                inner.insert(&index.name, Some(Value::Number(i as f64)));
+            }
+            self.body(&for_.body, &inner);
+        }
+    }
+
+    /// Renders what the caller of the current component passed for `slot`,
+    /// in the caller's own scope, or the slot's fallback when it passed
+    /// nothing (or outside of any component).
+    fn slot(&mut self, slot: &'a Slot, scope: &Scope<'a>) {
+        let frame = match self.stack.pop() {
+            Some(frame) => frame,
+            None => {
+                if let Some(fallback) = &slot.body {
+                    self.body(fallback, scope);
+                }
+                return;
+            }
+        };
+        let content: Vec<&'a Child> = match &slot.name {
+            None => frame.children.clone(),
+            Some(name) => frame
+                .slots
+                .get(name.name.as_str())
+                .map_or_else(Vec::new, |body| body.children.iter().collect()),
+        };
+
+        if content.is_empty() {
+            self.stack.push(frame);
+            if let Some(fallback) = &slot.body {
+                self.body(fallback, scope);
+            }
+        } else {
+            for child in content {
+                self.child(child, &frame.caller);
+            }
+            self.stack.push(frame);
+        }
+    }
+
+    fn element(&mut self, element: &'a Element, scope: &Scope<'a>) {
+        if element.tag == Tag::Html {
+            self.out.push_str("<!DOCTYPE html>");
+        }
+
+        write!(self.out, "<{}", element.tag).unwrap();
+        if let Some(attributes) = &element.attributes {
+            for (name, value) in self.resolve_attributes(attributes, scope) {
+                match value {
+                    Value::Bool(true) => write!(self.out, " {}", name).unwrap(),
+                    Value::Bool(false) => {}
+                    value => write!(
+                        self.out,
+                        " {}=\"{}\"",
+                        name,
+                        escape::attribute(&value.to_string())
+                    )
+                    .unwrap(),
+                }
+            }
+        }
+        self.out.push('>');
+
+        if element.tag.is_raw_text() {
+            let outer = self.raw_text.replace(element.tag.to_string());
+            self.body(&element.body, scope);
+            self.raw_text = outer;
+        } else {
+            self.body(&element.body, scope);
+        }
+
+        if !element.tag.is_self_closing() {
+            write!(self.out, "</{}>", element.tag).unwrap();
+        }
+    }
+
+    /// Expands a component call site: the attributes passed are bound to the
+    /// parameters of the matching definition, whose body is rendered with
+    /// them in scope.
+    fn component(&mut self, component: &'a ComponentExpr, scope: &Scope<'a>) {
+        let name = component.id.name.as_str();
+        let module = self.stack.last().map_or(0, |frame| frame.component.module);
+        let callee = match self.components[module].get(name) {
+            Some(callee) => *callee,
+            None => {
+                return self.report(Diagnostic::error(
+                    codes::UNKNOWN_COMPONENT,
+                    format!("cannot find component `{}`", name),
+                    component.id.loc.clone(),
+                ))
+            }
+        };
+        let def = callee.def;
+        if self
+            .stack
+            .iter()
+            .any(|frame| std::ptr::eq(frame.component.def, def))
+        {
+            return self.report(Diagnostic::error(
+                codes::RECURSIVE_COMPONENT,
+                format!("component `{}` renders itself", name),
+                component.id.loc.clone(),
+            ));
+        }
+
+        let arguments = check_arguments(def, component);
+        for diagnostic in arguments.into_iter().chain(check_slots(def, component)) {
+            self.report(diagnostic);
+        }
+
+        let attributes = component.attributes.as_ref();
+        let passed = attributes.map_or_else(Vec::new, |a| self.resolve_attributes(a, scope));
+        let inner: Scope = def
+            .attributes
+            .iter()
+            .map(|param| {
+                let name = param.name.as_str();
+                let value = passed
+                    .iter()
+                    .find(|(passed, _)| *passed == name)
+                    .map(|(_, value)| value.clone());
+                (name, value)
+            })
+            .collect();
+
+        let mut frame = Frame {
+            component: callee,
+            children: Vec::new(),
+            slots: HashMap::new(),
+            caller: scope.clone(),
+        };
+        for child in component.body.iter().flat_map(|body| &body.children) {
+            match child {
+                Child::Slot(Slot {
+                    name: Some(slot),
+                    body: Some(body),
+                    ..
+                }) => {
+                    frame.slots.insert(&slot.name, body);
+                }
+                child => frame.children.push(child),
+            }
+        }
+
+        self.instantiate(&inner, frame);
+    }
+
+    /// Evaluates attributes in source order. Only the first of several
+    /// attributes with the same name is kept, except for `class` whose values
+    /// are all merged into the first one. A boolean `class` cannot be merged.
+    fn resolve_attributes<'b>(
+        &mut self,
+        attributes: &'b Attributes,
+        scope: &Scope,
+    ) -> Vec<(&'b str, Value)> {
+        let mut resolved: Vec<(&str, Value)> = Vec::new();
+        for attribute in &attributes.attr {
+            let name = attribute.name.name.as_str();
+            let value = match self.attribute_value(name, &attribute.value, scope) {
+                Some(value) => value,
+                None => continue,
+            };
+            match resolved.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, class)) if name == "class" => match (&*class, value) {
+                    (Value::Bool(_), _) | (_, Value::Bool(_)) => self.report(Diagnostic::error(
+                        codes::TYPE_MISMATCH,
+                        "cannot merge a boolean `class` with another one",
+                        attribute.name.loc.clone(),
+                    )),
+                    (merged, value) => *class = Value::from(format!("{} {}", merged, value)),
+                },
+                Some(_) => {}
+                None => resolved.push((name, value)),
+            }
+        }
+        resolved
+    }
+
+    /// Resolves an attribute written as `name=value` or just `name`. The latter
+    /// is the value of the parameter `name` when there is one in scope (so that
+    /// `img [src]` is short for `img [src=src]`), and a boolean attribute
+    /// otherwise.
+    fn attribute_value(
+        &mut self,
+        name: &str,
+        value: &Option<Expr>,
+        scope: &Scope,
+    ) -> Option<Value> {
+        match value {
+            Some(expr) => self.eval(expr, scope),
+            None => match scope.get(name) {
+                Some(param) => param.clone(),
+                None => Some(Value::Bool(true)),
+            },
+        }
+    }
+
+    /// Renders a string literal, passing its text through `literal` and the
+    /// values interpolated in it through `value`. Interpolating a missing
+    /// value renders nothing.
+    fn interpolate(
+        &mut self,
+        segments: &[Segment],
+        scope: &Scope,
+        literal: impl Fn(&str) -> String,
+        value: impl Fn(&str) -> String,
+    ) -> String {
+        let mut out = String::new();
+        for segment in segments {
+            match segment {
+                Segment::Text(text) => out.push_str(&literal(text)),
+                Segment::Expr(expr) => {
+                    if let Some(diagnostic) = self.literal_brace(expr, scope) {
+                        self.report(diagnostic);
+                    } else if let Some(evaluated) = self.eval(expr, scope) {
+                        out.push_str(&value(&evaluated.to_string()));
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Code in `script` and `style` often has braces around a plain name, as
+    /// in `const {a} = o`, which read as an interpolation. When that name is
+    /// not defined, the error suggests escaping the brace instead.
+    fn literal_brace(&self, expr: &Expr, scope: &Scope) -> Option<Diagnostic> {
+        let tag = self.raw_text.as_ref()?;
+        match &expr.kind {
+            ExprKind::Var(name)
+                if !scope.contains_key(name.as_str()) && self.context.get(name).is_none() =>
+            {
+                Some(Diagnostic::error(
+                    codes::UNKNOWN_VARIABLE,
+                    format!(
+                        "cannot find `{}` in this scope, write `\\{{` for a literal brace in {}",
+                        name, tag
+                    ),
+                    expr.loc.clone(),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Evaluates `expr` as a condition. There, a name that is neither in
+    /// scope nor in the context is false rather than an error, so that render
+    /// data can leave out flags like `if is_admin`. Compared with `==` or `<`,
+    /// such a name is still reported.
+    fn truthy(&mut self, expr: &Expr, scope: &Scope) -> Option<bool> {
+        match &expr.kind {
+            ExprKind::Var(name)
+                if scope.get(name.as_str()).is_none() && self.context.get(name).is_none() =>
+            {
+                Some(false)
+            }
+            ExprKind::Not(operand) => self.truthy(operand, scope).map(|value| !value),
+            ExprKind::Binary(BinaryOp::And, lhs, rhs) => {
+                Some(self.truthy(lhs, scope)? && self.truthy(rhs, scope)?)
+            }
+            ExprKind::Binary(BinaryOp::Or, lhs, rhs) => {
+                Some(self.truthy(lhs, scope)? || self.truthy(rhs, scope)?)
+            }
+            _ => self.eval(expr, scope).map(|value| value.is_truthy()),
+        }
+    }
+
+    /// Evaluates `expr`, reporting variables that are neither in scope nor in
+    /// the context. A declared parameter the caller did not pass evaluates to
+    /// `None` too, but has been reported at the call site already.
+    fn eval(&mut self, expr: &Expr, scope: &Scope) -> Option<Value> {
+        match &expr.kind {
+            ExprKind::Str(segments) => Some(Value::String(self.interpolate(
+                segments,
+                scope,
+                str::to_owned,
+                str::to_owned,
+            ))),
+            ExprKind::Var(name) => match scope.get(name.as_str()) {
+                Some(value) => value.clone(),
+                None => self.context.get(name).or_else(|| {
+                    self.report(Diagnostic::error(
+                        codes::UNKNOWN_VARIABLE,
+                        format!("cannot find `{}` in this scope", name),
+                        expr.loc.clone(),
+                    ));
+                    None
+                }),
+            },
+            ExprKind::Field(map, field) => match self.eval(map, scope)? {
+                Value::Map(entries) => {
+                    let value = entries.get(&field.name).cloned();
+                    if value.is_none() {
+                        self.report(Diagnostic::error(
+                            codes::UNKNOWN_FIELD,
+                            format!("map has no entry `{}`", field.name),
+                            field.loc.clone(),
+                        ));
+                    }
+                    value
+                }
+                value => {
+                    self.report(Diagnostic::error(
+                        codes::TYPE_MISMATCH,
+                        format!(
+                            "cannot read `{}` from a {}, only from a map",
+                            field.name,
+                            value.type_name()
+                        ),
+                        field.loc.clone(),
+                    ));
+                    None
+                }
+            },
+            ExprKind::Number(value) => Some(Value::Number(*value)),
+            ExprKind::Bool(value) => Some(Value::Bool(*value)),
+            ExprKind::Not(_) | ExprKind::Binary(BinaryOp::And | BinaryOp::Or, _, _) => {
+                self.truthy(expr, scope).map(Value::Bool)
+            }
+            ExprKind::Binary(op, lhs, rhs) => {
+                let lhs = self.eval(lhs, scope)?;
+                let rhs = self.eval(rhs, scope)?;
+                let ordering = match op {
+                    BinaryOp::Eq => return Some(Value::Bool(lhs == rhs)),
+                    BinaryOp::Ne => return Some(Value::Bool(lhs != rhs)),
+                    _ => match (&lhs, &rhs) {
+                        (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+                        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                        _ => {
+                            self.report(Diagnostic::error(
+                                codes::TYPE_MISMATCH,
+                                format!(
+                                    "cannot compare a {} with a {} using `{}`",
+                                    lhs.type_name(),
+                                    rhs.type_name(),
+                                    op
+                                ),
+                                expr.loc.clone(),
+                            ));
+                            return None;
+                        }
+                    },
+                };
+                let result = match op {
+                    BinaryOp::Lt => ordering == Some(Ordering::Less),
+                    BinaryOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                    BinaryOp::Gt => ordering == Some(Ordering::Greater),
+                    _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+                };
+                Some(Value::Bool(result))
+            }
+        }
     }
 }
@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{ast::Module, utils::Loc};
+
+/// Stable identifiers for every diagnostic Hyper can emit. The prefix is the
+/// default severity: a lint denied by the `Linter` is an error but keeps its
+/// `W` code, so that a code always names the same rule.
+pub mod codes {
+    pub const UNEXPECTED_TOKEN: &str = "E0001";
+    pub const UNEXPECTED_EOF: &str = "E0002";
+    pub const UNTERMINATED_COMMENT: &str = "E0003";
+    pub const DUPLICATE_ATTRIBUTE: &str = "E0004";
+    pub const UNKNOWN_COMPONENT: &str = "E0005";
+    pub const UNKNOWN_PARAMETER: &str = "E0006";
+    pub const MISSING_PARAMETER: &str = "E0007";
+    pub const RECURSIVE_COMPONENT: &str = "E0008";
+    pub const UNKNOWN_ENTRY: &str = "E0009";
+    pub const UNKNOWN_SLOT: &str = "E0010";
+    pub const UNEXPECTED_CHILDREN: &str = "E0011";
+    pub const UNKNOWN_VARIABLE: &str = "E0012";
+    pub const NOT_ITERABLE: &str = "E0013";
+    pub const TYPE_MISMATCH: &str = "E0014";
+    pub const UNKNOWN_FIELD: &str = "E0015";
+    pub const UNKNOWN_MODULE: &str = "E0016";
+    pub const IMPORT_CYCLE: &str = "E0017";
+    pub const UNKNOWN_IMPORT: &str = "E0018";
+    pub const PRIVATE_COMPONENT: &str = "E0019";
+    pub const DUPLICATE_DEFINITION: &str = "E0020";
+    pub const VOID_CHILDREN: &str = "E0021";
+    pub const INVALID_CHILD: &str = "E0022";
+    pub const MISPLACED_METADATA: &str = "E0023";
+
+    pub const UNUSED_PARAMETER: &str = "W0001";
+    pub const SHADOWED_VARIABLE: &str = "W0002";
+    pub const MISSING_ALT: &str = "W0003";
+    pub const MISSING_LANG: &str = "W0004";
+    pub const UNLABELLED_CONTROL: &str = "W0005";
+    pub const SKIPPED_HEADING_LEVEL: &str = "W0006";
+    pub const EMPTY_LINK: &str = "W0007";
+    pub const UNKNOWN_LINT: &str = "W0008";
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub loc: Loc,
+    pub expected: Vec<String>,
+    /// The path of the imported module `loc` is in, `None` for the root
+    /// module.
+    pub module: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(code: &'static str, message: impl Into<String>, loc: Loc) -> Self {
+        Self {
+            severity: Severity::Error,
+            code,
+            message: message.into(),
+            loc,
+            expected: Vec::new(),
+            module: None,
+        }
+    }
+
+    pub fn warning(code: &'static str, message: impl Into<String>, loc: Loc) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(code, message, loc)
+        }
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = code;
+        self
+    }
+
+    pub fn with_expected(mut self, expected: Vec<String>) -> Self {
+        self.expected = expected;
+        self
+    }
+
+    /// Tags the diagnostic with the path of the module it was reported in.
+    /// The empty path of the root module leaves it untagged.
+    pub fn in_module(mut self, path: impl Into<String>) -> Self {
+        let path = path.into();
+        self.module = if path.is_empty() { None } else { Some(path) };
+        self
+    }
+
+    /// Like `in_module`, with the path of `module`.
+    pub fn in_module_of(self, module: &Module) -> Self {
+        self.in_module(module.path.as_str())
+    }
+
+    /// `line:column`, prefixed with the module path outside the root module.
+    pub fn location(&self) -> String {
+        match &self.module {
+            Some(module) => format!(
+                "{}:{}:{}",
+                module, self.loc.start.line, self.loc.start.column
+            ),
+            None => format!("{}:{}", self.loc.start.line, self.loc.start.column),
+        }
+    }
+
+    /// Renders the diagnostic together with the offending source line and a
+    /// caret underline spanning `loc`:
+    ///
+    /// ```text
+    /// error[E0001]: expected `}`, found `)`
+    ///  --> 2:18
+    ///   |
+    /// 2 |             html {)
+    ///   |                  ^
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let line_number = self.loc.start.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let line = source
+            .lines()
+            .nth(self.loc.start.line.saturating_sub(1) as usize)
+            .unwrap_or("");
+
+        let start = self.loc.start.column.max(1);
+        let end = if self.loc.end.line == self.loc.start.line {
+            self.loc.end.column
+        } else {
+            line.chars().count() + 1
+        };
+        let width = end.saturating_sub(start).max(1);
+        // Tabs are kept so that the caret lines up with the line above it.
+        let mut padding: String = line
+            .chars()
+            .take(start - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let missing = start - 1 - padding.chars().count();
+        padding.push_str(&" ".repeat(missing));
+
+        format!(
+            "{}\n{}--> {}\n{} |\n{} | {}\n{} | {}{}",
+            self,
+            gutter,
+            self.location(),
+            gutter,
+            line_number,
+            line,
+            gutter,
+            padding,
+            "^".repeat(width)
+        )
+    }
+
+    /// The diagnostic as a JSON object, for tools reading Hyper's output.
+    ///
+    /// ```text
+    /// {"severity":"warning","code":"W0003","message":"`img` has no `alt` text",
+    ///  "module":null,"start":{"line":5,"column":9},"end":{"line":5,"column":12},
+    ///  "expected":[]}
+    /// ```
+    pub fn to_json(&self) -> String {
+        let module = match &self.module {
+            Some(module) => json_string(module),
+            None => String::from("null"),
+        };
+        let expected = self
+            .expected
+            .iter()
+            .map(|e| json_string(e))
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "{{\"severity\":\"{}\",\"code\":{},\"message\":{},\"module\":{},\
+             \"start\":{{\"line\":{},\"column\":{}}},\"end\":{{\"line\":{},\"column\":{}}},\
+             \"expected\":[{}]}}",
+            self.severity,
+            json_string(self.code),
+            json_string(&self.message),
+            module,
+            self.loc.start.line,
+            self.loc.start.column,
+            self.loc.end.line,
+            self.loc.end.column,
+            expected
+        )
+    }
+}
+
+/// `diagnostics` as a JSON array.
+pub fn to_json(diagnostics: &[Diagnostic]) -> String {
+    format!(
+        "[{}]",
+        diagnostics
+            .iter()
+            .map(Diagnostic::to_json)
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::with_capacity(text.len() + 2);
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)
+    }
+}
+
+/// Returned by `Parser::parse` when the source is not valid Hyper.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub diagnostics: Vec<Diagnostic>,
+    source: String,
+    /// The sources of the imported modules, by path.
+    modules: HashMap<String, String>,
+}
+
+impl ParseError {
+    pub(crate) fn new(source: &str, diagnostics: Vec<Diagnostic>) -> Self {
+        Self {
+            diagnostics,
+            source: source.to_owned(),
+            modules: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn with_modules(mut self, modules: HashMap<String, String>) -> Self {
+        self.modules = modules;
+        self
+    }
+
+    /// The source of the root module.
+    pub fn source_text(&self) -> &str {
+        &self.source
+    }
+
+    /// The source of the module `diagnostic` was reported in.
+    pub fn source_of(&self, diagnostic: &Diagnostic) -> &str {
+        match &diagnostic.module {
+            Some(module) => self.modules.get(module).map_or("", String::as_str),
+            None => &self.source,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.diagnostics
+                .iter()
+                .map(|d| d.render(self.source_of(d)))
+                .collect::<Vec<String>>()
+                .join("\n\n")
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Returned by `Compiler::compile` when a program parses but cannot be
+/// rendered, e.g. because it uses an unknown component.
+#[derive(Debug, PartialEq)]
+pub struct CompileError {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl CompileError {
+    pub(crate) fn new(diagnostics: Vec<Diagnostic>) -> Self {
+        Self { diagnostics }
+    }
+
+    /// Renders every diagnostic against the `source` the program was parsed
+    /// from. Use `render_with` for programs spanning several modules.
+    pub fn render(&self, source: &str) -> String {
+        self.render_with(|_| source)
+    }
+
+    /// Renders every diagnostic against the source `sources` returns for the
+    /// module it was reported in (`None` for the root module).
+    pub fn render_with<'a>(&self, sources: impl Fn(Option<&str>) -> &'a str) -> String {
+        self.diagnostics
+            .iter()
+            .map(|d| d.render(sources(d.module.as_deref())))
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.diagnostics
+                .iter()
+                .map(|d| format!("{} at {}", d, d.location()))
+                .collect::<Vec<String>>()
+                .join("\n")
+        )
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+/// Returned by `Linter::with_config` for a config file it cannot read.
+#[derive(Debug, PartialEq)]
+pub struct ConfigError {
+    /// The line of the config file, starting at 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl ConfigError {
+    pub(crate) fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ConfigError {}
@@ -0,0 +1,48 @@
+/// Escapes text content, so that it can't open a tag or start an entity.
+pub(crate) fn text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes a value written between the double quotes of an attribute.
+pub(crate) fn attribute(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes the content of a raw text element (`script`, `style`). Entities
+/// are not decoded there, so the content is kept as is except for anything
+/// that would close the element early: `</script` becomes `<\/script`, which
+/// means the same in both JavaScript strings and CSS.
+pub(crate) fn raw_text(value: &str, tag: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(i) = rest.find("</") {
+        let after = &rest[i + 2..];
+        let closes = after
+            .get(..tag.len())
+            .is_some_and(|name| name.eq_ignore_ascii_case(tag));
+        escaped.push_str(&rest[..i]);
+        escaped.push_str(if closes { "<\\/" } else { "</" });
+        rest = after;
+    }
+    escaped.push_str(rest);
+    escaped
+}
@@ -0,0 +1,61 @@
+//! Token-level parsers shared by the grammar in `parser`.
+//!
+//! Names are always read as a whole identifier token before being looked at,
+//! so that a keyword or tag name never matches the prefix of a longer word
+//! (`head` in `header`, `def` in `default`).
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{alpha1, alphanumeric1},
+    combinator::{map_opt, recognize, verify},
+    multi::many0,
+    sequence::pair,
+};
+
+use crate::{ast::Tag, utils::*};
+
+/// An identifier token: a letter (or `-`) followed by letters, digits, `-`
+/// and `_`.
+pub(crate) fn ident(input: Span) -> PResult<Span> {
+    recognize(pair(
+        alt((alpha1, tag("-"))),
+        many0(alt((alphanumeric1, tag("-"), tag("_")))),
+    ))(input)
+}
+
+/// Words with a meaning of their own in a body, which cannot name a variable.
+const KEYWORDS: &[&str] = &[
+    "def", "use", "pub", "raw", "children", "slot", "for", "in", "if", "else", "true", "false",
+];
+
+pub(crate) fn is_keyword(word: &str) -> bool {
+    KEYWORDS.contains(&word)
+}
+
+/// Matches the reserved word `word`, and only as a whole identifier.
+pub(crate) fn keyword<'a>(word: &'static str) -> impl FnMut(Span<'a>) -> PResult<'a, Span<'a>> {
+    verify(ident, move |token: &Span| *token.fragment() == word)
+}
+
+/// Classifies an identifier token as an element name.
+///
+/// Tag names are case sensitive: only the lowercase spelling of a standard
+/// HTML element (`p`, `header`) is recognised, and anything capitalised is
+/// left for components. Lowercase names containing a hyphen are custom
+/// elements, as required by the HTML spec (`my-card`, `sl-button`).
+pub(crate) fn tag_name(input: Span) -> PResult<Tag> {
+    map_opt(ident, |token: Span| {
+        let name = *token.fragment();
+        Tag::from_name(name).or_else(|| {
+            let is_custom = name.starts_with(|c: char| c.is_ascii_lowercase())
+                && name.contains('-')
+                && !name.contains(|c: char| c.is_ascii_uppercase());
+            if is_custom {
+                Some(Tag::Custom(name.to_owned()))
+            } else {
+                None
+            }
+        })
+    })(input)
+}
@@ -1,12 +1,26 @@
 mod ast;
+mod checker;
 mod compiler;
+mod diagnostic;
+mod escape;
+mod lexer;
+mod lint;
+mod loader;
 mod parser;
 mod string;
 mod utils;
+mod validator;
+mod value;
 
 pub mod prelude {
     pub use crate::ast::*;
-    pub use crate::compiler::Compiler;
+    pub use crate::checker::Checker;
+    pub use crate::compiler::{Compiler, Renderer};
+    pub use crate::diagnostic::*;
+    pub use crate::lint::{walk, Level, Lint, LintContext, Linter};
+    pub use crate::loader::{FileLoader, MemoryLoader, ModuleLoader};
     pub use crate::parser::*;
     pub use crate::utils::*;
+    pub use crate::validator::Validator;
+    pub use crate::value::*;
 }
@@ -0,0 +1,538 @@
+//! Lints: markup that renders fine but is likely a mistake. The built-in
+//! lints are about accessibility, and more can be added to a `Linter` by
+//! implementing `Lint`.
+//!
+//! Every lint is allowed, warned about or denied. Levels are set on the
+//! `Linter` or by a config file, and the source can override them with
+//! comments: `#![deny(img-alt)]` for its whole module, or `#[allow(img-alt)]`
+//! for the definition or element that follows.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::{
+    ast::*,
+    diagnostic::{codes, ConfigError, Diagnostic},
+    utils::{Loc, Position},
+};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Level {
+    /// The lint is not reported.
+    Allow,
+    /// The lint is reported as a warning.
+    Warn,
+    /// The lint is reported as an error.
+    Deny,
+}
+
+impl Level {
+    pub fn from_name(name: &str) -> Option<Level> {
+        match name {
+            "allow" => Some(Level::Allow),
+            "warn" => Some(Level::Warn),
+            "deny" => Some(Level::Deny),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Level::Allow => write!(f, "allow"),
+            Level::Warn => write!(f, "warn"),
+            Level::Deny => write!(f, "deny"),
+        }
+    }
+}
+
+/// A check run over every module of a program. A lint only reports what it
+/// finds; the `Linter` decides at which level, if any, it is shown.
+pub trait Lint {
+    /// The name levels are set by, like `img-alt`.
+    fn name(&self) -> &'static str;
+
+    /// The code of the diagnostics the lint reports, its name by default. It
+    /// does not change with the level: a denied lint keeps its code.
+    fn code(&self) -> &'static str {
+        self.name()
+    }
+
+    fn default_level(&self) -> Level {
+        Level::Warn
+    }
+
+    /// Called once for every module, before any of its elements.
+    fn check_module(&self, _module: &Module, _cx: &mut LintContext) {}
+
+    /// Called for every element of every module, in source order.
+    fn check_element(&self, _element: &Element, _cx: &mut LintContext) {}
+}
+
+/// What a lint sees of the program while checking it.
+pub struct LintContext<'a> {
+    ancestors: Vec<&'a Element>,
+    reports: Vec<(Loc, String)>,
+}
+
+impl<'a> LintContext<'a> {
+    /// The elements around the one being checked, outermost first.
+    pub fn ancestors(&self) -> &[&'a Element] {
+        &self.ancestors
+    }
+
+    pub fn report(&mut self, loc: Loc, message: impl Into<String>) {
+        self.reports.push((loc, message.into()));
+    }
+}
+
+/// Calls `f` with every element in `children` and the elements around it,
+/// outermost first.
+pub fn walk<'a>(
+    children: impl IntoIterator<Item = &'a Child>,
+    f: &mut dyn FnMut(&'a Element, &[&'a Element]),
+) {
+    let mut ancestors = Vec::new();
+    for child in children {
+        walk_child(child, &mut ancestors, f);
+    }
+}
+
+fn walk_child<'a>(
+    child: &'a Child,
+    ancestors: &mut Vec<&'a Element>,
+    f: &mut dyn FnMut(&'a Element, &[&'a Element]),
+) {
+    if let Child::Element(element) = child {
+        f(element, ancestors);
+        ancestors.push(element);
+    }
+    for body in child.bodies() {
+        for child in &body.children {
+            walk_child(child, ancestors, f);
+        }
+    }
+    if let Child::Element(_) = child {
+        ancestors.pop();
+    }
+}
+
+/// The top-level children of `module`: its markup and the bodies of its
+/// definitions, in source order.
+fn children(module: &Module) -> impl Iterator<Item = &Child> {
+    module
+        .statements
+        .iter()
+        .flat_map(|statement| match statement {
+            Statement::Component(def) => def.body.children.iter().collect(),
+            Statement::Markup(child) => vec![child],
+            Statement::Import(_) | Statement::Error(_) => Vec::new(),
+        })
+}
+
+pub struct Linter {
+    lints: Vec<Box<dyn Lint>>,
+    levels: HashMap<String, Level>,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Linter {
+    /// A linter running the built-in lints at their default level.
+    pub fn new() -> Self {
+        Self {
+            lints: vec![
+                Box::new(ImgAlt),
+                Box::new(HtmlLang),
+                Box::new(ControlLabel),
+                Box::new(HeadingOrder),
+                Box::new(LinkText),
+            ],
+            levels: HashMap::new(),
+        }
+    }
+
+    pub fn with_lint(mut self, lint: impl Lint + 'static) -> Self {
+        self.lints.push(Box::new(lint));
+        self
+    }
+
+    pub fn with_level(mut self, name: &str, level: Level) -> Self {
+        self.levels.insert(name.to_owned(), level);
+        self
+    }
+
+    /// Sets levels from a config file with a `name = level` line per lint.
+    /// Lines starting with `#` are comments. Lints must be added before the
+    /// config that names them.
+    ///
+    /// ```text
+    /// img-alt = deny
+    /// heading-order = allow
+    /// ```
+    pub fn with_config(mut self, config: &str) -> Result<Self, ConfigError> {
+        for (index, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| ConfigError::new(index + 1, message);
+
+            let (name, level) = line
+                .split_once('=')
+                .ok_or_else(|| error(String::from("expected `name = level`")))?;
+            let (name, level) = (name.trim(), level.trim());
+            if !self.is_known(name) {
+                return Err(error(format!("unknown lint `{}`", name)));
+            }
+            let level = Level::from_name(level).ok_or_else(|| {
+                error(format!(
+                    "unknown level `{}`, expected `allow`, `warn` or `deny`",
+                    level
+                ))
+            })?;
+            self.levels.insert(name.to_owned(), level);
+        }
+        Ok(self)
+    }
+
+    fn is_known(&self, name: &str) -> bool {
+        self.lints.iter().any(|lint| lint.name() == name)
+    }
+
+    /// Runs every lint over every module of `program`. The diagnostics of
+    /// each module are sorted by position.
+    pub fn lint(&self, program: &Program) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for module in &program.modules {
+            let mut found = Vec::new();
+            let overrides = self.overrides(module, &mut found);
+
+            for lint in &self.lints {
+                let mut cx = LintContext {
+                    ancestors: Vec::new(),
+                    reports: Vec::new(),
+                };
+                lint.check_module(module, &mut cx);
+                walk(children(module), &mut |element, ancestors| {
+                    cx.ancestors = ancestors.to_vec();
+                    lint.check_element(element, &mut cx);
+                });
+
+                for (loc, message) in cx.reports {
+                    match self.level(lint.as_ref(), &overrides, &loc) {
+                        Level::Allow => {}
+                        Level::Warn => found.push(Diagnostic::warning(lint.code(), message, loc)),
+                        Level::Deny => found.push(Diagnostic::error(lint.code(), message, loc)),
+                    }
+                }
+            }
+
+            found.sort_by_key(|d| position(&d.loc.start));
+            diagnostics.extend(found.into_iter().map(|d| d.in_module_of(module)));
+        }
+        diagnostics
+    }
+
+    /// The level `lint` reports `loc` at: the one set on the linter, then the
+    /// one set for the module, then the ones set for the nodes around `loc`,
+    /// outermost first.
+    fn level(&self, lint: &dyn Lint, overrides: &[Override], loc: &Loc) -> Level {
+        let mut level = self
+            .levels
+            .get(lint.name())
+            .copied()
+            .unwrap_or_else(|| lint.default_level());
+        for o in overrides {
+            let applies = o.target.is_none_or(|target| contains(target, loc));
+            if o.name == lint.name() && applies {
+                level = o.level;
+            }
+        }
+        level
+    }
+
+    /// Reads the lint levels set by comments in `module`, reporting the ones
+    /// naming unknown lints. Module-wide levels come first, followed by the
+    /// ones of each node in source order.
+    fn overrides<'a>(&self, module: &'a Module, found: &mut Vec<Diagnostic>) -> Vec<Override<'a>> {
+        let mut nodes = Vec::new();
+        for statement in &module.statements {
+            match statement {
+                Statement::Component(def) => {
+                    nodes.push(&def.loc);
+                    child_locs(&def.body.children, &mut nodes);
+                }
+                Statement::Markup(child) => child_locs(std::slice::from_ref(child), &mut nodes),
+                Statement::Import(_) | Statement::Error(_) => {}
+            }
+        }
+
+        let mut overrides = Vec::new();
+        for comment in &module.comments {
+            let attribute = match comment.kind {
+                CommentKind::Line => parse_attribute(&comment.text),
+                CommentKind::Block => None,
+            };
+            let (inner, level, names) = match attribute {
+                Some(attribute) => attribute,
+                None => continue,
+            };
+            // An outer attribute applies to the first node after it.
+            let target = if inner {
+                None
+            } else {
+                let next = nodes
+                    .iter()
+                    .filter(|loc| position(&loc.start) >= position(&comment.loc.end))
+                    .min_by_key(|loc| position(&loc.start));
+                match next {
+                    Some(loc) => Some(*loc),
+                    None => continue,
+                }
+            };
+            for name in names {
+                if self.is_known(name) {
+                    overrides.push(Override {
+                        name,
+                        level,
+                        target,
+                    });
+                } else {
+                    found.push(Diagnostic::warning(
+                        codes::UNKNOWN_LINT,
+                        format!("unknown lint `{}`", name),
+                        comment.loc.clone(),
+                    ));
+                }
+            }
+        }
+        overrides.sort_by_key(|o| o.target.map(|loc| position(&loc.start)));
+        overrides
+    }
+}
+
+/// A lint level set by a comment, for the node at `target` or for the whole
+/// module.
+struct Override<'a> {
+    name: &'a str,
+    level: Level,
+    target: Option<&'a Loc>,
+}
+
+/// Reads `[level(name, ...)]`, or `![level(name, ...)]` for a whole module,
+/// from the text of a line comment.
+fn parse_attribute(text: &str) -> Option<(bool, Level, Vec<&str>)> {
+    let text = text.trim();
+    let (inner, text) = match text.strip_prefix('!') {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+    let text = text.strip_prefix('[')?.strip_suffix(']')?;
+    let (level, names) = text.split_once('(')?;
+    let level = Level::from_name(level.trim())?;
+    let names = names
+        .trim_end()
+        .strip_suffix(')')?
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect();
+    Some((inner, level, names))
+}
+
+fn child_locs<'a>(children: &'a [Child], locs: &mut Vec<&'a Loc>) {
+    for child in children {
+        locs.push(child.loc());
+        for body in child.bodies() {
+            child_locs(&body.children, locs);
+        }
+    }
+}
+
+fn position(position: &Position) -> (u32, usize) {
+    (position.line, position.column)
+}
+
+fn contains(outer: &Loc, inner: &Loc) -> bool {
+    position(&outer.start) <= position(&inner.start) && position(&inner.end) <= position(&outer.end)
+}
+
+struct ImgAlt;
+
+impl Lint for ImgAlt {
+    fn name(&self) -> &'static str {
+        "img-alt"
+    }
+
+    fn code(&self) -> &'static str {
+        codes::MISSING_ALT
+    }
+
+    fn check_element(&self, element: &Element, cx: &mut LintContext) {
+        if element.tag == Tag::Img && element.attribute("alt").is_none() {
+            cx.report(element.tag_loc(), "`img` has no `alt` text");
+        }
+    }
+}
+
+struct HtmlLang;
+
+impl Lint for HtmlLang {
+    fn name(&self) -> &'static str {
+        "html-lang"
+    }
+
+    fn code(&self) -> &'static str {
+        codes::MISSING_LANG
+    }
+
+    fn check_element(&self, element: &Element, cx: &mut LintContext) {
+        if element.tag == Tag::Html && element.attribute("lang").is_none() {
+            cx.report(element.tag_loc(), "`html` has no `lang` attribute");
+        }
+    }
+}
+
+struct ControlLabel;
+
+impl Lint for ControlLabel {
+    fn name(&self) -> &'static str {
+        "control-label"
+    }
+
+    fn code(&self) -> &'static str {
+        codes::UNLABELLED_CONTROL
+    }
+
+    fn check_module(&self, module: &Module, cx: &mut LintContext) {
+        // The `for` of every `label` in the module, or `None` when one of
+        // them is not a string literal and any `id` may be labelled.
+        let mut labels = Some(HashSet::new());
+        walk(children(module), &mut |element, _| {
+            if element.tag == Tag::Label {
+                match element.attribute("for").map(Attribute::literal) {
+                    Some(Some(id)) => {
+                        if let Some(labels) = &mut labels {
+                            labels.insert(id);
+                        }
+                    }
+                    Some(None) => labels = None,
+                    None => {}
+                }
+            }
+        });
+
+        walk(children(module), &mut |element, ancestors| {
+            if !matches!(element.tag, Tag::Input | Tag::Select | Tag::Textarea) {
+                return;
+            }
+            if element.tag == Tag::Input {
+                let kind = element.attribute("type").and_then(Attribute::literal);
+                if let Some("hidden" | "submit" | "reset" | "button" | "image") = kind {
+                    return;
+                }
+            }
+            let labelled = ancestors.iter().any(|a| a.tag == Tag::Label)
+                || has_name(element)
+                || match (element.attribute("id").map(Attribute::literal), &labels) {
+                    (Some(Some(id)), Some(labels)) => labels.contains(id),
+                    (Some(_), _) => true,
+                    (None, _) => false,
+                };
+            if !labelled {
+                cx.report(element.tag_loc(), format!("`{}` has no label", element.tag));
+            }
+        });
+    }
+}
+
+struct HeadingOrder;
+
+impl Lint for HeadingOrder {
+    fn name(&self) -> &'static str {
+        "heading-order"
+    }
+
+    fn code(&self) -> &'static str {
+        codes::SKIPPED_HEADING_LEVEL
+    }
+
+    fn check_module(&self, module: &Module, cx: &mut LintContext) {
+        // The top-level markup of a module is one document, and the body of
+        // each definition another.
+        let mut documents = vec![Vec::new()];
+        for statement in &module.statements {
+            match statement {
+                Statement::Markup(child) => documents[0].push(child),
+                Statement::Component(def) => documents.push(def.body.children.iter().collect()),
+                Statement::Import(_) | Statement::Error(_) => {}
+            }
+        }
+
+        for document in documents {
+            let mut previous: Option<u8> = None;
+            walk(document, &mut |element, _| {
+                if let Some(level) = element.tag.heading_level() {
+                    if let Some(previous) = previous.filter(|previous| level > previous + 1) {
+                        cx.report(
+                            element.tag_loc(),
+                            format!(
+                                "`{}` follows `h{}`, skipping a heading level",
+                                element.tag, previous
+                            ),
+                        );
+                    }
+                    previous = Some(level);
+                }
+            });
+        }
+    }
+}
+
+struct LinkText;
+
+impl Lint for LinkText {
+    fn name(&self) -> &'static str {
+        "link-text"
+    }
+
+    fn code(&self) -> &'static str {
+        codes::EMPTY_LINK
+    }
+
+    fn check_element(&self, element: &Element, cx: &mut LintContext) {
+        if element.tag == Tag::A && !has_name(element) && !has_text(&element.body.children) {
+            cx.report(element.tag_loc(), "link has no text");
+        }
+    }
+}
+
+/// Whether `element` is named by an ARIA attribute or a `title`.
+fn has_name(element: &Element) -> bool {
+    ["aria-label", "aria-labelledby", "title"]
+        .iter()
+        .any(|name| element.attribute(name).is_some())
+}
+
+/// Whether `children` render any text. Values, components and slots are
+/// only known when rendering, so they count as text.
+fn has_text(children: &[Child]) -> bool {
+    children.iter().any(|child| match child {
+        Child::Text(text) | Child::Raw(text) => text.segments.iter().any(|segment| match segment {
+            Segment::Text(text) => !text.trim().is_empty(),
+            Segment::Expr(_) => true,
+        }),
+        Child::Expr(_) | Child::Component(_) | Child::Slot(_) | Child::Error(_) => true,
+        Child::Element(element) if element.tag == Tag::Img => element
+            .attribute("alt")
+            .is_some_and(|alt| alt.literal() != Some("")),
+        Child::Element(element) => has_name(element) || has_text(&element.body.children),
+        Child::For(_) | Child::If(_) => child.bodies().iter().any(|body| has_text(&body.children)),
+    })
+}
@@ -0,0 +1,272 @@
+//! Resolution of `use` imports: the modules a program imports are loaded
+//! through a `ModuleLoader` and parsed along with it.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::{
+    ast::{ComponentDef, Module, Program, Statement},
+    diagnostic::{codes, Diagnostic},
+    parser::parse_module_source,
+};
+
+/// Finds the source of a module from its `::` separated path.
+pub trait ModuleLoader {
+    fn load(&self, path: &str) -> io::Result<String>;
+}
+
+/// Loads `ui::avatar` from `<root>/ui/avatar.hy`.
+pub struct FileLoader {
+    root: PathBuf,
+}
+
+impl FileLoader {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl ModuleLoader for FileLoader {
+    fn load(&self, path: &str) -> io::Result<String> {
+        let mut file = self.root.clone();
+        file.extend(path.split("::"));
+        file.set_extension("hy");
+        fs::read_to_string(file)
+    }
+}
+
+/// Serves modules from memory, e.g. for tests or sources bundled with an
+/// application.
+#[derive(Default)]
+pub struct MemoryLoader {
+    modules: HashMap<String, String>,
+}
+
+impl MemoryLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_module(mut self, path: &str, source: &str) -> Self {
+        self.modules.insert(path.to_owned(), source.to_owned());
+        self
+    }
+}
+
+impl ModuleLoader for MemoryLoader {
+    fn load(&self, path: &str) -> io::Result<String> {
+        self.modules
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no module `{}`", path)))
+    }
+}
+
+/// Parses `source` and every module it imports, directly or not. Returns
+/// the program, the diagnostics of all its modules and the sources of the
+/// imported ones, by path.
+pub(crate) fn load_program(
+    source: &str,
+    loader: &dyn ModuleLoader,
+) -> (Program, Vec<Diagnostic>, HashMap<String, String>) {
+    let mut load = Load {
+        loader,
+        modules: Vec::new(),
+        diagnostics: Vec::new(),
+        sources: HashMap::new(),
+        stack: Vec::new(),
+    };
+    let (module, diagnostics) = parse_module_source(source);
+    load.diagnostics.extend(diagnostics);
+    load.module(module);
+    load.check_imports();
+
+    let program = Program {
+        modules: load.modules,
+    };
+    (program, load.diagnostics, load.sources)
+}
+
+struct Load<'a> {
+    loader: &'a dyn ModuleLoader,
+    modules: Vec<Module>,
+    diagnostics: Vec<Diagnostic>,
+    sources: HashMap<String, String>,
+    /// The paths of the modules whose imports are being loaded, innermost
+    /// last.
+    stack: Vec<String>,
+}
+
+impl<'a> Load<'a> {
+    /// Adds `module` to the program, then loads what it imports.
+    fn module(&mut self, module: Module) {
+        let index = self.modules.len();
+        self.stack.push(module.path.clone());
+        self.modules.push(module);
+
+        let imports: Vec<_> = self.modules[index]
+            .statements
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::Import(import) => Some((import.module_path(), import.path_loc())),
+                _ => None,
+            })
+            .collect();
+        for (path, loc) in imports {
+            let in_module = |diagnostic: Diagnostic| match self.stack.last() {
+                Some(module) => diagnostic.in_module(module.as_str()),
+                None => diagnostic,
+            };
+
+            if let Some(start) = self.stack.iter().position(|m| *m == path) {
+                let mut cycle: Vec<&str> = self.stack[start..].iter().map(String::as_str).collect();
+                cycle.push(&path);
+                let diagnostic = in_module(Diagnostic::error(
+                    codes::IMPORT_CYCLE,
+                    format!("modules import each other: {}", cycle.join(" -> ")),
+                    loc,
+                ));
+                self.diagnostics.push(diagnostic);
+                continue;
+            }
+            if self.modules.iter().any(|m| m.path == path) {
+                continue;
+            }
+
+            match self.loader.load(&path) {
+                Ok(source) => {
+                    let (mut module, diagnostics) = parse_module_source(&source);
+                    module.path = path.clone();
+                    self.diagnostics
+                        .extend(diagnostics.into_iter().map(|d| d.in_module(path.as_str())));
+                    self.sources.insert(path, source);
+                    self.module(module);
+                }
+                Err(error) => {
+                    let diagnostic = in_module(Diagnostic::error(
+                        codes::UNKNOWN_MODULE,
+                        format!("cannot load module `{}`: {}", path, error),
+                        loc,
+                    ));
+                    self.diagnostics.push(diagnostic);
+                }
+            }
+        }
+
+        self.stack.pop();
+    }
+
+    /// Reports the imports naming a component their module does not export.
+    fn check_imports(&mut self) {
+        for module in &self.modules {
+            for statement in &module.statements {
+                let import = match statement {
+                    Statement::Import(import) => import,
+                    _ => continue,
+                };
+                let path = import.module_path();
+                if !self.modules.iter().any(|m| m.path == path) {
+                    continue;
+                }
+                let name = &import.name.name;
+                let (code, message) = match resolve_export(&self.modules, &path, name) {
+                    Ok(_) => continue,
+                    Err(Export::Unknown) => (
+                        codes::UNKNOWN_IMPORT,
+                        format!("module `{}` has no component `{}`", path, name),
+                    ),
+                    Err(Export::Private) => (
+                        codes::PRIVATE_COMPONENT,
+                        format!("component `{}` is private to module `{}`", name, path),
+                    ),
+                };
+                let diagnostic = Diagnostic::error(code, message, import.name.loc.clone());
+                self.diagnostics.push(diagnostic.in_module_of(module));
+            }
+        }
+    }
+}
+
+/// Why a component cannot be imported from a module.
+pub(crate) enum Export {
+    Unknown,
+    Private,
+}
+
+/// A component definition, with the index of the module defining it.
+#[derive(Clone, Copy)]
+pub(crate) struct Component<'a> {
+    pub module: usize,
+    pub def: &'a ComponentDef,
+}
+
+/// The components usable in each of `modules`, by name: its own and the ones
+/// it imports. Imports that do not resolve are left out.
+pub(crate) fn components(modules: &[Module]) -> Vec<HashMap<&str, Component<'_>>> {
+    modules
+        .iter()
+        .enumerate()
+        .map(|(index, module)| {
+            let mut components = HashMap::new();
+            for statement in &module.statements {
+                match statement {
+                    Statement::Import(import) => {
+                        let path = import.module_path();
+                        let name = import.name.name.as_str();
+                        if let Ok((module, def)) = resolve_export(modules, &path, name) {
+                            components.insert(name, Component { module, def });
+                        }
+                    }
+                    Statement::Component(def) => {
+                        let component = Component { module: index, def };
+                        components.insert(def.id.name.as_str(), component);
+                    }
+                    Statement::Markup(_) | Statement::Error(_) => {}
+                }
+            }
+            components
+        })
+        .collect()
+}
+
+/// Finds the component `name` exported by the module at `path`, along with
+/// the index of the module defining it. It is either defined there with
+/// `pub def`, or re-exported with `pub use` from another module.
+pub(crate) fn resolve_export<'a>(
+    modules: &'a [Module],
+    path: &str,
+    name: &str,
+) -> Result<(usize, &'a ComponentDef), Export> {
+    let mut seen = Vec::new();
+    let mut path = path.to_owned();
+    loop {
+        let index = match modules.iter().position(|m| m.path == path) {
+            Some(index) if !seen.contains(&index) => index,
+            _ => return Err(Export::Unknown),
+        };
+        seen.push(index);
+
+        let mut next = None;
+        for statement in &modules[index].statements {
+            match statement {
+                Statement::Component(def) if def.id.name == name => {
+                    return if def.public {
+                        Ok((index, def))
+                    } else {
+                        Err(Export::Private)
+                    };
+                }
+                Statement::Import(import) if import.name.name == name => {
+                    if !import.public {
+                        return Err(Export::Private);
+                    }
+                    next = Some(import.module_path());
+                }
+                _ => {}
+            }
+        }
+        path = next.ok_or(Export::Unknown)?;
+    }
+}
@@ -1,203 +1,208 @@
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::{
-        complete::{alpha1, alphanumeric1},
-        streaming::char,
-    },
-    combinator::{map, opt, recognize, value},
+    character::complete::{char, digit1, space0, space1},
+    combinator::{cut, eof, map, map_res, opt, recognize, success, value, verify},
+    error::{context, ErrorKind, ParseError as _},
     multi::{many0, many1, separated_list0, separated_list1},
-    sequence::{delimited, pair, preceded, separated_pair, tuple},
-    IResult,
+    sequence::{delimited, pair, preceded, terminated, tuple},
 };
-use std::collections::HashMap;
+use std::cell::RefCell;
 
-use crate::{ast::*, string::parse_string, utils::*};
+use crate::{
+    ast::*,
+    diagnostic::{codes, Diagnostic, ParseError},
+    lexer::{ident, is_keyword, keyword, tag_name},
+    loader::{load_program, ModuleLoader},
+    string::{parse_block_string, parse_string},
+    utils::*,
+};
 
-fn parse_tag(input: Span) -> IResult<Span, Tag> {
-    alt((
-        alt((
-            value(Tag::Html, tag("html")),
-            value(Tag::Base, tag("base")),
-            value(Tag::Head, tag("head")),
-            value(Tag::Link, tag("link")),
-            value(Tag::Meta, tag("meta")),
-            value(Tag::Style, tag("style")),
-            value(Tag::Title, tag("title")),
-            value(Tag::Body, tag("body")),
-            value(Tag::Address, tag("address")),
-            value(Tag::Article, tag("article")),
-            value(Tag::Aside, tag("aside")),
-        )),
-        alt((
-            value(Tag::Footer, tag("footer")),
-            value(Tag::Header, tag("header")),
-            value(Tag::H1, tag("h1")),
-            value(Tag::H2, tag("h2")),
-            value(Tag::H3, tag("h3")),
-            value(Tag::H4, tag("h4")),
-            value(Tag::H5, tag("h5")),
-            value(Tag::H6, tag("h6")),
-            value(Tag::Main, tag("main")),
-            value(Tag::Nav, tag("nav")),
-            value(Tag::Section, tag("section")),
-        )),
-        alt((
-            value(Tag::Blockquote, tag("blockquote")),
-            value(Tag::Dd, tag("dd")),
-            value(Tag::Div, tag("div")),
-            value(Tag::Dl, tag("dl")),
-            value(Tag::Dt, tag("dt")),
-            value(Tag::Figcaption, tag("figcaption")),
-            value(Tag::Figure, tag("figure")),
-            value(Tag::Hr, tag("hr")),
-            value(Tag::Li, tag("li")),
-            value(Tag::Ol, tag("ol")),
-            value(Tag::P, tag("P")),
-        )),
-        alt((
-            value(Tag::Pre, tag("pre")),
-            value(Tag::Ul, tag("ul")),
-            value(Tag::A, tag("A")),
-            value(Tag::Abbr, tag("abbr")),
-            value(Tag::B, tag("B")),
-            value(Tag::Bdi, tag("bdi")),
-            value(Tag::Bdo, tag("bdo")),
-            value(Tag::Br, tag("br")),
-            value(Tag::Cite, tag("cite")),
-            value(Tag::Code, tag("code")),
-            value(Tag::Data, tag("data")),
-        )),
-        alt((
-            value(Tag::Dfm, tag("dfm")),
-            value(Tag::Em, tag("em")),
-            value(Tag::I, tag("I")),
-            value(Tag::Kbd, tag("kbd")),
-            value(Tag::Mark, tag("mark")),
-            value(Tag::Q, tag("Q")),
-            value(Tag::Rb, tag("rb")),
-            value(Tag::Rp, tag("rp")),
-            value(Tag::Rt, tag("rt")),
-            value(Tag::Rtc, tag("rtc")),
-            value(Tag::Ruby, tag("ruby")),
-        )),
-        alt((
-            value(Tag::S, tag("S")),
-            value(Tag::Samp, tag("samp")),
-            value(Tag::Small, tag("small")),
-            value(Tag::Span, tag("span")),
-            value(Tag::Strong, tag("strong")),
-            value(Tag::Sub, tag("sub")),
-            value(Tag::Sup, tag("sup")),
-            value(Tag::Time, tag("time")),
-            value(Tag::U, tag("U")),
-            value(Tag::Var, tag("var")),
-            value(Tag::Wbr, tag("wbr")),
-        )),
-        alt((
-            value(Tag::Area, tag("area")),
-            value(Tag::Audio, tag("audio")),
-            value(Tag::Img, tag("img")),
-            value(Tag::Map, tag("map")),
-            value(Tag::Track, tag("track")),
-            value(Tag::Video, tag("video")),
-            value(Tag::Embed, tag("embed")),
-            value(Tag::Iframe, tag("iframe")),
-            value(Tag::Object, tag("object")),
-            value(Tag::Param, tag("param")),
-            value(Tag::Picture, tag("picture")),
-        )),
-        alt((
-            value(Tag::Portal, tag("portal")),
-            value(Tag::Source, tag("source")),
-            value(Tag::Svg, tag("svg")),
-            value(Tag::Math, tag("math")),
-            value(Tag::Canvas, tag("canvas")),
-            value(Tag::Noscript, tag("noscript")),
-            value(Tag::Script, tag("script")),
-            value(Tag::Del, tag("del")),
-            value(Tag::Ins, tag("ins")),
-            value(Tag::Caption, tag("caption")),
-            value(Tag::Col, tag("col")),
-        )),
-        alt((
-            value(Tag::Colgroup, tag("colgroup")),
-            value(Tag::Table, tag("table")),
-            value(Tag::Tbody, tag("tbody")),
-            value(Tag::Td, tag("td")),
-            value(Tag::Tfoot, tag("tfoot")),
-            value(Tag::Th, tag("th")),
-            value(Tag::Thead, tag("thead")),
-            value(Tag::Tr, tag("tr")),
-            value(Tag::Button, tag("button")),
-            value(Tag::Datalist, tag("datalist")),
-            value(Tag::Fieldset, tag("fieldset")),
-        )),
-        alt((
-            value(Tag::Form, tag("form")),
-            value(Tag::Input, tag("input")),
-            value(Tag::Label, tag("label")),
-            value(Tag::Legend, tag("legend")),
-            value(Tag::Meter, tag("meter")),
-            value(Tag::Otgroup, tag("otgroup")),
-            value(Tag::Option, tag("option")),
-            value(Tag::Output, tag("output")),
-            value(Tag::Progress, tag("progress")),
-            value(Tag::Select, tag("select")),
-            value(Tag::Textarea, tag("textarea")),
-        )),
-        alt((
-            value(Tag::Details, tag("details")),
-            value(Tag::Dialog, tag("dialog")),
-            value(Tag::Menu, tag("menu")),
-            value(Tag::Summary, tag("summary")),
-            value(Tag::Slot, tag("slot")),
-            value(Tag::Template, tag("template")),
-        )),
-    ))(input)
+fn component_name(input: Span) -> PResult<Id> {
+    context(
+        "component name",
+        verify(id, |id| id.name.starts_with(|c: char| c.is_uppercase())),
+    )(input)
 }
 
-fn id(input: Span) -> IResult<Span, Id> {
-    located(
-        recognize(pair(
-            alt((alpha1, tag("-"))),
-            many0(alt((alphanumeric1, tag("-")))),
-        )),
-        |loc, name| Id {
+fn id(input: Span) -> PResult<Id> {
+    context(
+        "identifier",
+        located(ident, |loc, name| Id {
             loc,
             name: (*name.fragment()).to_owned(),
-        },
+        }),
     )(input)
 }
 
-fn key_value(input: Span) -> IResult<Span, (Id, String)> {
-    separated_pair(id, preceded(sp, char('=')), preceded(sp, parse_string))(input)
+/// An identifier that can name a parameter or loop variable.
+fn variable(input: Span) -> PResult<Id> {
+    context("variable", verify(id, |id| !is_keyword(&id.name)))(input)
+}
+
+/// Parses an expression. From loosest to tightest binding: `||`, `&&`, the
+/// comparisons, then `!`, literals, variables and parenthesised expressions.
+pub(crate) fn parse_expr(input: Span) -> PResult<Expr> {
+    binary(input, parse_and, |i| value(BinaryOp::Or, tag("||"))(i))
+}
+
+fn parse_and(input: Span) -> PResult<Expr> {
+    binary(input, parse_comparison, |i| {
+        value(BinaryOp::And, tag("&&"))(i)
+    })
+}
+
+fn parse_comparison(input: Span) -> PResult<Expr> {
+    binary(input, parse_unary, |i| {
+        alt((
+            value(BinaryOp::Eq, tag("==")),
+            value(BinaryOp::Ne, tag("!=")),
+            value(BinaryOp::Le, tag("<=")),
+            value(BinaryOp::Ge, tag(">=")),
+            value(BinaryOp::Lt, tag("<")),
+            value(BinaryOp::Gt, tag(">")),
+        ))(i)
+    })
+}
+
+/// Parses `operand (operator operand)*`, associating to the left.
+fn binary<'a>(
+    input: Span<'a>,
+    operand: fn(Span<'a>) -> PResult<'a, Expr>,
+    operator: fn(Span<'a>) -> PResult<'a, BinaryOp>,
+) -> PResult<'a, Expr> {
+    let (mut input, mut lhs) = operand(input)?;
+    loop {
+        match pair(preceded(sp, operator), preceded(sp, cut(operand)))(input) {
+            Ok((rest, (op, rhs))) => {
+                let loc = Loc {
+                    start: lhs.loc.start.clone(),
+                    end: rhs.loc.end.clone(),
+                };
+                lhs = Expr {
+                    loc,
+                    kind: ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)),
+                };
+                input = rest;
+            }
+            Err(nom::Err::Error(_)) => return Ok((input, lhs)),
+            Err(e) => return Err(e),
+        }
+    }
 }
 
-fn parse_attr(input: Span) -> IResult<Span, HashMap<String, String>> {
-    map(
-        separated_list1(preceded(sp, char(';')), preceded(sp, key_value)),
-        |tuple_vec| {
-            tuple_vec
-                .into_iter()
-                .map(|(k, v)| (String::from(k.name), v))
-                .collect()
+fn parse_unary(input: Span) -> PResult<Expr> {
+    alt((
+        located(
+            preceded(char('!'), preceded(sp, cut(parse_unary))),
+            |loc, expr| Expr {
+                loc,
+                kind: ExprKind::Not(Box::new(expr)),
+            },
+        ),
+        delimited(
+            char('('),
+            preceded(sp, cut(parse_expr)),
+            cut(preceded(sp, char(')'))),
+        ),
+        located(
+            alt((
+                map(context("string", parse_string), ExprKind::Str),
+                map(context("number", parse_number), ExprKind::Number),
+                map(keyword("true"), |_| ExprKind::Bool(true)),
+                map(keyword("false"), |_| ExprKind::Bool(false)),
+            )),
+            |loc, kind| Expr { loc, kind },
+        ),
+        |i| {
+            let (rest, var) = variable(i)?;
+            parse_fields(rest, var)
         },
+    ))(input)
+}
+
+/// Parses the `.name` accesses following the variable `var`, as in
+/// `user.address.city`.
+fn parse_fields(mut input: Span, var: Id) -> PResult<Expr> {
+    let mut expr = Expr {
+        loc: var.loc,
+        kind: ExprKind::Var(var.name),
+    };
+    loop {
+        let (rest, field) = match preceded(char('.'), cut(id))(input) {
+            Ok(field) => field,
+            Err(nom::Err::Error(_)) => return Ok((input, expr)),
+            Err(e) => return Err(e),
+        };
+        expr = Expr {
+            loc: Loc {
+                start: expr.loc.start.clone(),
+                end: field.loc.end.clone(),
+            },
+            kind: ExprKind::Field(Box::new(expr), field),
+        };
+        input = rest;
+    }
+}
+
+fn parse_number(input: Span) -> PResult<f64> {
+    map_res(
+        recognize(pair(digit1, opt(pair(char('.'), digit1)))),
+        |number: Span| number.fragment().parse::<f64>(),
     )(input)
 }
 
-fn parse_attributes(input: Span) -> IResult<Span, Attributes> {
-    located(delimited(char('['), parse_attr, char(']')), |loc, attr| {
-        Attributes { loc, attr }
-    })(input)
+/// An attribute is either `name=value` or just `name`, which is a boolean
+/// attribute, or inside a component a shorthand for `name=name`.
+fn key_value(input: Span) -> PResult<Attribute> {
+    located(
+        pair(
+            id,
+            opt(preceded(preceded(sp, char('=')), preceded(sp, parse_expr))),
+        ),
+        |loc, (name, value)| Attribute { loc, name, value },
+    )(input)
 }
 
-fn parse_element(input: Span) -> IResult<Span, Element> {
+fn parse_attr(input: Span) -> PResult<Vec<Attribute>> {
+    separated_list1(preceded(sp, char(';')), preceded(sp, key_value))(input)
+}
+
+/// Parses `[...]`, reporting attributes given more than once. Repeated
+/// `class` attributes are fine: their values are merged when compiling.
+fn parse_attributes(input: Span) -> PResult<Attributes> {
+    let (rest, attributes) = located(
+        preceded(
+            char('['),
+            cut(terminated(parse_attr, preceded(sp, char(']')))),
+        ),
+        |loc, attr| Attributes { loc, attr },
+    )(input)?;
+
+    for (i, attribute) in attributes.attr.iter().enumerate() {
+        let name = &attribute.name.name;
+        if name != "class" && attributes.attr[..i].iter().any(|a| &a.name.name == name) {
+            input.extra.report(Diagnostic::error(
+                codes::DUPLICATE_ATTRIBUTE,
+                format!("attribute `{}` is given more than once", name),
+                attribute.name.loc.clone(),
+            ));
+        }
+    }
+
+    Ok((rest, attributes))
+}
+
+fn parse_element(input: Span) -> PResult<Element> {
     located(
         tuple((
-            parse_tag,
-            preceded(sp, opt(parse_attributes)),
-            preceded(sp, parse_body),
+            context("element", tag_name),
+            opt(preceded(sp, parse_attributes)),
+            alt((
+                preceded(sp, parse_body),
+                parse_inline_body,
+                parse_empty_body,
+            )),
         )),
         |loc, (tag, attributes, body)| Element {
             loc,
@@ -208,16 +213,36 @@ fn parse_element(input: Span) -> IResult<Span, Element> {
     )(input)
 }
 
-fn parse_text_node(input: Span) -> IResult<Span, TextNode> {
-    located(parse_string, |loc, value| TextNode { loc, value })(input)
+/// A single child written on the same line as its parent, as in
+/// `title "Hyper!"` or `html head title "Hyper!"`, is sugar for a body
+/// holding just that child.
+fn parse_inline_body(input: Span) -> PResult<Body> {
+    map(preceded(space0, parse_child), |child| Body {
+        loc: child.loc().clone(),
+        children: vec![child],
+    })(input)
+}
+
+fn parse_empty_body(input: Span) -> PResult<Body> {
+    located(success(()), |loc, _| Body {
+        loc,
+        children: Vec::new(),
+    })(input)
+}
+
+fn parse_text_node(input: Span) -> PResult<TextNode> {
+    located(
+        context("string", alt((parse_block_string, parse_string))),
+        |loc, segments| TextNode { loc, segments },
+    )(input)
 }
 
-fn parse_component_expr(input: Span) -> IResult<Span, ComponentExpr> {
+fn parse_component_expr(input: Span) -> PResult<ComponentExpr> {
     located(
         tuple((
-            id,
-            preceded(sp, opt(parse_attributes)),
-            preceded(sp, opt(parse_body)),
+            component_name,
+            opt(preceded(sp, parse_attributes)),
+            opt(preceded(sp, parse_body)),
         )),
         |loc, (id, attributes, body)| ComponentExpr {
             loc,
@@ -228,75 +253,327 @@ fn parse_component_expr(input: Span) -> IResult<Span, ComponentExpr> {
     )(input)
 }
 
-fn parse_child(input: Span) -> IResult<Span, Child> {
+fn parse_raw_node(input: Span) -> PResult<TextNode> {
+    preceded(terminated(keyword("raw"), sp), parse_text_node)(input)
+}
+
+/// `children` or `slot name`, with an optional fallback body. `slot` followed
+/// by anything but a name is the HTML `<slot>` element, as in `slot {}`.
+fn parse_slot(input: Span) -> PResult<Slot> {
+    located(
+        pair(
+            alt((
+                map(keyword("children"), |_| None),
+                map(preceded(pair(keyword("slot"), space1), id), Some),
+            )),
+            opt(preceded(sp, parse_body)),
+        ),
+        |loc, (name, body)| Slot { loc, name, body },
+    )(input)
+}
+
+fn parse_for(input: Span) -> PResult<For> {
+    located(
+        preceded(
+            keyword("for"),
+            cut(|input| {
+                let (input, (binding, index, iterable)) = tuple((
+                    preceded(sp, variable),
+                    opt(preceded(preceded(sp, char(',')), preceded(sp, variable))),
+                    preceded(
+                        preceded(sp, context("`in`", keyword("in"))),
+                        preceded(sp, parse_expr),
+                    ),
+                ))(input)?;
+                let ids: Vec<&Id> = std::iter::once(&binding).chain(&index).collect();
+                let (input, body) = scoped(&ids, preceded(sp, parse_body))(input)?;
+                let (input, else_body) = opt(preceded(
+                    preceded(sp, keyword("else")),
+                    cut(preceded(sp, parse_body)),
+                ))(input)?;
+                Ok((input, (binding, index, iterable, body, else_body)))
+            }),
+        ),
+        |loc, (binding, index, iterable, body, else_body)| For {
+            loc,
+            binding,
+            index,
+            iterable,
+            body,
+            else_body,
+        },
+    )(input)
+}
+
+/// Runs `parser` with `ids` in scope, see `parse_bound_expr`.
+fn scoped<'a, 'b, O>(
+    ids: &'b [&'b Id],
+    mut parser: impl FnMut(Span<'a>) -> PResult<'a, O> + 'b,
+) -> impl FnMut(Span<'a>) -> PResult<'a, O> + 'b {
+    move |input: Span<'a>| {
+        input.extra.bind(ids);
+        let result = parser(input);
+        input.extra.unbind(ids.len());
+        result
+    }
+}
+
+/// A variable rendered as text, possibly followed by `.name` accesses. A
+/// bare name followed by `[` or `{` is a misspelt element or component
+/// rather than a variable, and is left for them to report.
+fn parse_child_expr(input: Span) -> PResult<Expr> {
+    let (rest, name) = verify(ident, |name: &Span| !is_keyword(name.fragment()))(input)?;
+    let next = rest.fragment().trim_start_matches([' ', '\t']);
+    if next.starts_with(['[', '{']) {
+        return Err(nom::Err::Error(SyntaxError::from_error_kind(
+            input,
+            ErrorKind::Verify,
+        )));
+    }
+    let var = Id {
+        loc: Loc {
+            start: input.into(),
+            end: rest.into(),
+        },
+        name: (*name.fragment()).to_owned(),
+    };
+    parse_fields(rest, var)
+}
+
+fn parse_if(input: Span) -> PResult<If> {
+    located(
+        tuple((
+            parse_branch,
+            many0(preceded(
+                preceded(sp, keyword("else")),
+                preceded(sp, parse_branch),
+            )),
+            opt(preceded(
+                preceded(sp, keyword("else")),
+                cut(preceded(sp, parse_body)),
+            )),
+        )),
+        |loc, (first, rest, else_body)| {
+            let mut branches = vec![first];
+            branches.extend(rest);
+            If {
+                loc,
+                branches,
+                else_body,
+            }
+        },
+    )(input)
+}
+
+fn parse_branch(input: Span) -> PResult<Branch> {
+    located(
+        preceded(
+            keyword("if"),
+            cut(pair(preceded(sp, parse_expr), preceded(sp, parse_body))),
+        ),
+        |loc, (condition, body)| Branch {
+            loc,
+            condition,
+            body,
+        },
+    )(input)
+}
+
+/// A parameter or loop variable in scope, which takes precedence over the
+/// element of the same name: in `def Card = [title] { h1 title }`, `title`
+/// is the parameter. With attributes or a body, it is still the element.
+fn parse_bound_expr(input: Span) -> PResult<Expr> {
+    let (_, name) = ident(input)?;
+    if !input.extra.is_bound(name.fragment()) {
+        return Err(nom::Err::Error(SyntaxError::from_error_kind(
+            input,
+            ErrorKind::Verify,
+        )));
+    }
+    parse_child_expr(input)
+}
+
+fn parse_child(input: Span) -> PResult<Child> {
     alt((
         map(parse_text_node, Child::Text),
+        map(parse_raw_node, Child::Raw),
+        map(parse_slot, Child::Slot),
+        map(parse_bound_expr, Child::Expr),
         map(parse_element, Child::Element),
         map(parse_component_expr, Child::Component),
+        map(parse_for, Child::For),
+        map(parse_if, Child::If),
+        map(parse_child_expr, Child::Expr),
     ))(input)
 }
 
-fn parse_body(input: Span) -> IResult<Span, Body> {
+fn parse_body(input: Span) -> PResult<Body> {
     located(
-        delimited(
+        preceded(
             char('{'),
-            many0(preceded(sp, parse_child)),
-            preceded(sp, char('}')),
+            many_recover(
+                preceded(sp, parse_child),
+                preceded(sp, close('}')),
+                skip_child,
+                |loc| Child::Error(ErrorNode { loc }),
+            ),
         ),
         |loc, children| Body { loc, children },
     )(input)
 }
 
-fn parse_component_def_attr(input: Span) -> IResult<Span, Vec<Id>> {
-    delimited(
+fn parse_component_def_attr(input: Span) -> PResult<Vec<Id>> {
+    preceded(
         char('['),
-        separated_list0(preceded(sp, char(';')), preceded(sp, id)),
-        char(']'),
+        cut(terminated(
+            separated_list0(preceded(sp, char(';')), preceded(sp, id)),
+            preceded(sp, char(']')),
+        )),
+    )(input)
+}
+
+/// `pub`, making the statement it starts visible to other modules.
+fn parse_visibility(input: Span) -> PResult<bool> {
+    map(opt(terminated(keyword("pub"), sp)), |public| {
+        public.is_some()
+    })(input)
+}
+
+fn parse_component_def(input: Span) -> PResult<ComponentDef> {
+    // The location starts at the definition itself, after any leading
+    // comments, so that a `#[allow(...)]` before it targets the definition.
+    delimited(
+        sp,
+        located(
+            pair(
+                parse_visibility,
+                preceded(
+                    context("`def`", keyword("def")),
+                    cut(|input| {
+                        let (input, (id, attributes)) = pair(
+                            preceded(sp, component_name),
+                            preceded(
+                                preceded(sp, char('=')),
+                                preceded(sp, parse_component_def_attr),
+                            ),
+                        )(input)?;
+                        let ids: Vec<&Id> = attributes.iter().collect();
+                        let (input, body) = scoped(&ids, preceded(sp, parse_body))(input)?;
+                        Ok((input, (id, attributes, body)))
+                    }),
+                ),
+            ),
+            |loc, (public, (id, attributes, body))| ComponentDef {
+                loc,
+                public,
+                id,
+                attributes,
+                body,
+            },
+        ),
+        opt(sp),
     )(input)
 }
 
-fn parse_component_def(input: Span) -> IResult<Span, ComponentDef> {
+fn parse_import(input: Span) -> PResult<Import> {
     located(
         delimited(
             sp,
-            tuple((
-                preceded(tag("def"), preceded(sp, id)),
+            pair(
+                parse_visibility,
                 preceded(
-                    preceded(sp, char('=')),
-                    preceded(sp, parse_component_def_attr),
+                    context("`use`", keyword("use")),
+                    cut(preceded(
+                        sp,
+                        pair(many1(terminated(id, tag("::"))), component_name),
+                    )),
                 ),
-                preceded(sp, parse_body),
-            )),
+            ),
             opt(sp),
         ),
-        |loc, (id, attributes, body)| ComponentDef {
+        |loc, (public, (path, name))| Import {
             loc,
-            id,
-            attributes,
-            body,
+            public,
+            path,
+            name,
         },
     )(input)
 }
 
-fn parse_statements(input: Span) -> IResult<Span, Vec<Statement>> {
-    many1(map(parse_component_def, |st| Statement::Component(st)))(input)
+fn parse_statement(input: Span) -> PResult<Statement> {
+    alt((
+        map(parse_import, Statement::Import),
+        map(parse_component_def, Statement::Component),
+        map(delimited(sp, parse_child, opt(sp)), Statement::Markup),
+    ))(input)
 }
 
-fn parse_module(input: Span) -> IResult<Span, Module> {
+fn parse_statements(input: Span) -> PResult<Vec<Statement>> {
+    many_recover(parse_statement, preceded(sp, eof), skip_statement, |loc| {
+        Statement::Error(ErrorNode { loc })
+    })(input)
+}
+
+fn parse_module(input: Span) -> PResult<Module> {
     located(parse_statements, |loc, statements| Module {
+        path: String::new(),
         loc,
         statements,
+        comments: Vec::new(),
     })(input)
 }
 
+/// Parses a single module, recovering from syntax errors.
+pub(crate) fn parse_module_source(source: &str) -> (Module, Vec<Diagnostic>) {
+    let recorded = RefCell::new(Recorded::default());
+    let input = Span::new_extra(source, State::new(&recorded));
+    let (_, mut module) = parse_module(input).expect("module parsing recovers from every error");
+
+    let Recorded {
+        mut diagnostics,
+        mut comments,
+        ..
+    } = recorded.into_inner();
+    diagnostics.sort_by_key(|d| (d.loc.start.line, d.loc.start.column));
+    comments.sort_by_key(|c| (c.loc.start.line, c.loc.start.column));
+    module.comments = comments;
+    (module, diagnostics)
+}
+
 pub struct Parser;
 
 impl Parser {
-    pub fn parse(source: &str) -> Result<Program, String> {
-        let result = map(parse_module, |module| Program { modules: module })(source.into());
-        match result {
-            Ok((_, content)) => Ok(content),
-            Err(e) => Err(format!("Failed to parse source {}", e)),
+    /// Parses `source`, failing with every syntax error found in it.
+    pub fn parse(source: &str) -> Result<Program, ParseError> {
+        let (program, diagnostics) = Parser::parse_with_recovery(source);
+        if diagnostics.is_empty() {
+            Ok(program)
+        } else {
+            Err(ParseError::new(source, diagnostics))
+        }
+    }
+
+    /// Parses `source` without stopping at the first syntax error. Broken
+    /// children and statements are skipped up to the next `}` or statement and
+    /// kept in the returned `Program` as error nodes, next to the diagnostics
+    /// describing them.
+    pub fn parse_with_recovery(source: &str) -> (Program, Vec<Diagnostic>) {
+        let (module, diagnostics) = parse_module_source(source);
+        let program = Program {
+            modules: vec![module],
+        };
+        (program, diagnostics)
+    }
+
+    /// Parses `source` as the root module of a program, loading the modules
+    /// it imports with `use` through `loader`. Fails with the syntax errors
+    /// of every module, and with the imports that could not be resolved.
+    pub fn parse_program(source: &str, loader: &dyn ModuleLoader) -> Result<Program, ParseError> {
+        let (program, diagnostics, sources) = load_program(source, loader);
+        if diagnostics.is_empty() {
+            Ok(program)
+        } else {
+            Err(ParseError::new(source, diagnostics).with_modules(sources))
         }
     }
 }
@@ -3,21 +3,21 @@
 
 use nom::{
     branch::alt,
-    bytes::streaming::{is_not, take_while_m_n},
-    character::streaming::{char, multispace1},
-    combinator::{map, map_opt, map_res, value, verify},
-    error::{FromExternalError, ParseError},
-    multi::fold_many0,
-    sequence::{delimited, preceded},
-    IResult,
+    bytes::complete::{is_not, tag, take_while_m_n},
+    character::complete::{char, multispace1},
+    combinator::{cut, eof, map, map_opt, map_res, not, recognize, value, verify},
+    multi::{fold_many0, many1_count},
+    sequence::{delimited, preceded, terminated},
+    Parser,
 };
 
-use crate::utils::Span;
+use crate::{
+    ast::{Expr, Segment},
+    parser::parse_expr,
+    utils::{sp, PResult, Span, SyntaxError},
+};
 
-fn parse_unicode<'a, E>(input: Span<'a>) -> IResult<Span<'a>, char, E>
-where
-    E: ParseError<Span<'a>> + FromExternalError<Span<'a>, std::num::ParseIntError>,
-{
+fn parse_unicode(input: Span) -> PResult<char> {
     let parse_hex = take_while_m_n(1, 6, |c: char| c.is_ascii_hexdigit());
 
     let parse_delimited_hex = preceded(char('u'), delimited(char('{'), parse_hex, char('}')));
@@ -26,13 +26,10 @@ where
         u32::from_str_radix(hex.fragment(), 16)
     });
 
-    map_opt(parse_u32, |value| std::char::from_u32(value))(input)
+    map_opt(parse_u32, std::char::from_u32)(input)
 }
 
-fn parse_escaped_char<'a, E>(input: Span<'a>) -> IResult<Span<'a>, char, E>
-where
-    E: ParseError<Span<'a>> + FromExternalError<Span<'a>, std::num::ParseIntError>,
-{
+fn parse_escaped_char(input: Span) -> PResult<char> {
     preceded(
         char('\\'),
         alt((
@@ -45,52 +42,180 @@ where
             value('\\', char('\\')),
             value('/', char('/')),
             value('"', char('"')),
+            value('{', char('{')),
+            value('}', char('}')),
         )),
     )(input)
 }
 
-fn parse_escaped_whitespace<'a, E: ParseError<Span<'a>>>(
-    input: Span<'a>,
-) -> IResult<Span<'a>, Span<'a>, E> {
+fn parse_escaped_whitespace(input: Span) -> PResult<Span> {
     preceded(char('\\'), multispace1)(input)
 }
 
-fn parse_literal<'a, E: ParseError<Span<'a>>>(input: Span<'a>) -> IResult<Span<'a>, Span<'a>, E> {
-    let not_quote_slash = is_not("\"\\");
+fn parse_literal(input: Span) -> PResult<Span> {
+    let not_quote_slash_brace = is_not("\"\\{");
+
+    verify(not_quote_slash_brace, |s: &Span| !s.fragment().is_empty())(input)
+}
 
-    verify(not_quote_slash, |s: &Span| !s.fragment().is_empty())(input)
+/// Like `parse_literal`, but lone quotes are allowed up to the closing `"""`.
+fn parse_block_literal(input: Span) -> PResult<Span> {
+    recognize(many1_count(alt((
+        is_not("\"\\{"),
+        terminated(tag("\""), not(tag("\"\""))),
+    ))))(input)
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 enum StringFragment<'a> {
     Literal(Span<'a>),
     EscapedChar(char),
     EscapedWS,
+    Interpolation(Expr),
 }
 
-fn parse_fragment<'a, E>(input: Span<'a>) -> IResult<Span<'a>, StringFragment<'a>, E>
+/// An expression interpolated as `{expr}`. A `{` that is followed by
+/// whitespace, or does not start an expression closed by `}`, is plain text,
+/// so that CSS and JavaScript code such as `a{color:red}` or `{"a": 1}` is
+/// kept as is. A literal `{name}` is written `\{name}`.
+fn parse_interpolation(input: Span) -> PResult<Expr> {
+    let result = preceded(
+        terminated(char('{'), not(alt((multispace1, tag("}"), eof)))),
+        terminated(preceded(sp, parse_expr), preceded(sp, char('}'))),
+    )(input);
+    match result {
+        Err(nom::Err::Failure(error)) => Err(nom::Err::Error(error)),
+        result => result,
+    }
+}
+
+fn parse_fragment<'a, L>(literal: L) -> impl FnMut(Span<'a>) -> PResult<'a, StringFragment<'a>>
 where
-    E: ParseError<Span<'a>> + FromExternalError<Span<'a>, std::num::ParseIntError>,
+    L: Parser<Span<'a>, Span<'a>, SyntaxError<'a>>,
 {
     alt((
-        map(parse_literal, StringFragment::Literal),
+        map(literal, StringFragment::Literal),
         map(parse_escaped_char, StringFragment::EscapedChar),
         value(StringFragment::EscapedWS, parse_escaped_whitespace),
-    ))(input)
+        map(parse_interpolation, StringFragment::Interpolation),
+        map(tag("{"), StringFragment::Literal),
+    ))
 }
 
-pub(crate) fn parse_string<'a, E>(input: Span<'a>) -> IResult<Span<'a>, String, E>
+fn build_string<'a, L>(literal: L) -> impl FnMut(Span<'a>) -> PResult<'a, Vec<Segment>>
 where
-    E: ParseError<Span<'a>> + FromExternalError<Span<'a>, std::num::ParseIntError>,
+    L: Parser<Span<'a>, Span<'a>, SyntaxError<'a>>,
 {
-    let build_string = fold_many0(parse_fragment, String::new(), |mut string, fragment| {
-        match fragment {
-            StringFragment::Literal(s) => string.push_str(s.fragment()),
-            StringFragment::EscapedChar(c) => string.push(c),
-            StringFragment::EscapedWS => {}
+    fold_many0(
+        parse_fragment(literal),
+        Vec::new(),
+        |mut segments, fragment| {
+            match fragment {
+                StringFragment::Literal(s) => push_text(&mut segments, s.fragment()),
+                StringFragment::EscapedChar(c) => {
+                    push_text(&mut segments, c.encode_utf8(&mut [0; 4]))
+                }
+                StringFragment::EscapedWS => {}
+                StringFragment::Interpolation(expr) => segments.push(Segment::Expr(expr)),
+            }
+            segments
+        },
+    )
+}
+
+/// Appends `text`, merging it with the text segment before it if any.
+fn push_text(segments: &mut Vec<Segment>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match segments.last_mut() {
+        Some(Segment::Text(last)) => last.push_str(text),
+        _ => segments.push(Segment::Text(text.to_owned())),
+    }
+}
+
+pub(crate) fn parse_string(input: Span) -> PResult<Vec<Segment>> {
+    delimited(char('"'), build_string(parse_literal), cut(char('"')))(input)
+}
+
+/// Parses a `"""` delimited string, which may span many lines and contain
+/// lone quotes. The indentation shared by its lines is stripped, see `dedent`.
+pub(crate) fn parse_block_string(input: Span) -> PResult<Vec<Segment>> {
+    map(
+        delimited(
+            tag("\"\"\""),
+            build_string(parse_block_literal),
+            cut(tag("\"\"\"")),
+        ),
+        dedent,
+    )(input)
+}
+
+/// Removes the leading whitespace common to every non-blank line. A line
+/// break right after the opening quotes and a blank line before the closing
+/// ones are dropped; text starting right after the opening quotes does not
+/// count towards the common indentation. An interpolation counts as text.
+fn dedent(segments: Vec<Segment>) -> Vec<Segment> {
+    // Split into lines, each a list of segments.
+    let mut lines: Vec<Vec<Segment>> = vec![Vec::new()];
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => {
+                for (i, line) in text.split('\n').enumerate() {
+                    if i > 0 {
+                        lines.push(Vec::new());
+                    }
+                    push_text(lines.last_mut().unwrap(), line);
+                }
+            }
+            expr => lines.last_mut().unwrap().push(expr),
         }
-        string
-    });
+    }
+
+    let first_inline = !lines[0].is_empty();
+    if !first_inline && lines.len() > 1 {
+        lines.remove(0);
+    }
+    if lines.len() > 1 && lines.last().is_some_and(|l| is_blank(l)) {
+        lines.pop();
+    }
+
+    let skip = if first_inline { 1 } else { 0 };
+    let indent = lines
+        .iter()
+        .skip(skip)
+        .filter(|line| !is_blank(line))
+        .map(|line| match line.first() {
+            Some(Segment::Text(text)) => text.len() - text.trim_start_matches([' ', '\t']).len(),
+            _ => 0,
+        })
+        .min()
+        .unwrap_or(0);
+
+    let mut dedented = Vec::new();
+    for (i, line) in lines.into_iter().enumerate() {
+        if i > 0 {
+            push_text(&mut dedented, "\n");
+        }
+        if i >= skip && is_blank(&line) {
+            continue;
+        }
+        for (j, segment) in line.into_iter().enumerate() {
+            match segment {
+                Segment::Text(text) if j == 0 && i >= skip => {
+                    push_text(&mut dedented, &text[indent..])
+                }
+                Segment::Text(text) => push_text(&mut dedented, &text),
+                expr => dedented.push(expr),
+            }
+        }
+    }
+    dedented
+}
 
-    delimited(char('"'), build_string, char('"'))(input)
+fn is_blank(line: &[Segment]) -> bool {
+    line.iter().all(|segment| match segment {
+        Segment::Text(text) => text.trim().is_empty(),
+        Segment::Expr(_) => false,
+    })
 }
@@ -1,7 +1,24 @@
-use nom::{bytes::complete::take_while, error::ParseError, IResult, Parser};
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_until},
+    character::complete::{char, multispace1, not_line_ending},
+    combinator::{recognize, rest, value},
+    error::{ContextError, ErrorKind, FromExternalError, ParseError},
+    multi::many0_count,
+    sequence::preceded,
+    IResult, Parser,
+};
 use nom_locate::{position, LocatedSpan};
+use std::cell::RefCell;
 
-pub type Span<'a> = LocatedSpan<&'a str>;
+use crate::{
+    ast::{Comment, CommentKind, Id},
+    diagnostic::{codes, Diagnostic},
+};
+
+pub type Span<'a> = LocatedSpan<&'a str, State<'a>>;
+
+pub(crate) type PResult<'a, O> = IResult<Span<'a>, O, SyntaxError<'a>>;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Loc {
@@ -15,8 +32,8 @@ pub struct Position {
     pub column: usize,
 }
 
-impl From<nom_locate::LocatedSpan<&str>> for Position {
-    fn from(span: nom_locate::LocatedSpan<&str>) -> Self {
+impl<X> From<nom_locate::LocatedSpan<&str, X>> for Position {
+    fn from(span: nom_locate::LocatedSpan<&str, X>) -> Self {
         Self {
             line: span.location_line(),
             column: span.get_utf8_column(),
@@ -24,6 +41,163 @@ impl From<nom_locate::LocatedSpan<&str>> for Position {
     }
 }
 
+/// Everything the parser collects on the side: diagnostics reported by
+/// parsers that recovered from an error, and comments skipped as whitespace.
+#[derive(Debug, Default)]
+pub(crate) struct Recorded {
+    pub diagnostics: Vec<Diagnostic>,
+    pub comments: Vec<Comment>,
+    /// The parameters and loop variables in scope, innermost last.
+    pub bindings: Vec<String>,
+}
+
+/// Carried alongside every `Span` so that parsers can record what they skip.
+#[derive(Debug, Clone, Copy)]
+pub struct State<'a>(&'a RefCell<Recorded>);
+
+impl<'a> State<'a> {
+    pub(crate) fn new(recorded: &'a RefCell<Recorded>) -> Self {
+        Self(recorded)
+    }
+
+    /// Records a diagnostic, ignoring repeated reports at the same position
+    /// (e.g. every unclosed block reaching the end of input).
+    pub(crate) fn report(&self, diagnostic: Diagnostic) {
+        let diagnostics = &mut self.0.borrow_mut().diagnostics;
+        if diagnostics
+            .iter()
+            .all(|d| d.loc.start != diagnostic.loc.start)
+        {
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    /// Brings `ids` into scope until the matching `unbind`.
+    pub(crate) fn bind(&self, ids: &[&Id]) {
+        let bindings = &mut self.0.borrow_mut().bindings;
+        bindings.extend(ids.iter().map(|id| id.name.clone()));
+    }
+
+    pub(crate) fn unbind(&self, count: usize) {
+        let bindings = &mut self.0.borrow_mut().bindings;
+        bindings.truncate(bindings.len() - count);
+    }
+
+    /// Whether `name` is a parameter or loop variable in scope.
+    pub(crate) fn is_bound(&self, name: &str) -> bool {
+        self.0.borrow().bindings.iter().any(|b| b == name)
+    }
+
+    /// Records a comment, ignoring the ones seen again after backtracking.
+    fn comment(&self, comment: Comment) {
+        let comments = &mut self.0.borrow_mut().comments;
+        if comments.iter().all(|c| c.loc.start != comment.loc.start) {
+            comments.push(comment);
+        }
+    }
+}
+
+/// The error type threaded through every parser. It remembers the furthest
+/// position reached and what would have been accepted there, so that the
+/// branches of an `alt` can be merged into a single "expected one of" message.
+#[derive(Debug)]
+pub(crate) struct SyntaxError<'a> {
+    pub span: Span<'a>,
+    pub expected: Vec<String>,
+}
+
+impl<'a> SyntaxError<'a> {
+    fn new(span: Span<'a>, expected: Vec<String>) -> Self {
+        Self { span, expected }
+    }
+
+    pub fn into_diagnostic(self) -> Diagnostic {
+        let start: Position = self.span.into();
+        let fragment = *self.span.fragment();
+        let word: String = fragment
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+            .collect();
+        let found = match fragment.chars().next() {
+            Some(_) if !word.is_empty() => Some(word),
+            Some(c) => Some(c.to_string()),
+            None => None,
+        };
+        let end = Position {
+            line: start.line,
+            column: start.column + found.as_ref().map_or(0, |f| f.chars().count()),
+        };
+        let loc = Loc { start, end };
+
+        let (code, found) = match found {
+            Some(token) => (
+                codes::UNEXPECTED_TOKEN,
+                format!("`{}`", token.escape_default()),
+            ),
+            None => (codes::UNEXPECTED_EOF, String::from("end of input")),
+        };
+        let message = match self.expected.as_slice() {
+            [] => format!("unexpected {}", found),
+            [one] => format!("expected {}, found {}", one, found),
+            many => format!("expected one of {}, found {}", many.join(", "), found),
+        };
+
+        Diagnostic::error(code, message, loc).with_expected(self.expected)
+    }
+}
+
+impl<'a> ParseError<Span<'a>> for SyntaxError<'a> {
+    fn from_error_kind(input: Span<'a>, kind: ErrorKind) -> Self {
+        let expected = match kind {
+            ErrorKind::Eof => vec![String::from("end of input")],
+            _ => Vec::new(),
+        };
+        Self::new(input, expected)
+    }
+
+    fn append(_: Span<'a>, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn from_char(input: Span<'a>, c: char) -> Self {
+        Self::new(input, vec![format!("`{}`", c)])
+    }
+
+    fn or(mut self, other: Self) -> Self {
+        let (offset, other_offset) = (self.span.location_offset(), other.span.location_offset());
+        if other_offset > offset {
+            other
+        } else if offset > other_offset {
+            self
+        } else {
+            for expected in other.expected {
+                if !self.expected.contains(&expected) {
+                    self.expected.push(expected);
+                }
+            }
+            self
+        }
+    }
+}
+
+impl<'a> ContextError<Span<'a>> for SyntaxError<'a> {
+    /// Labels an error with `ctx` when it happened right where the labelled
+    /// parser started; deeper errors keep their more precise expectations.
+    fn add_context(input: Span<'a>, ctx: &'static str, other: Self) -> Self {
+        if input.location_offset() == other.span.location_offset() {
+            Self::new(input, vec![ctx.to_owned()])
+        } else {
+            other
+        }
+    }
+}
+
+impl<'a, E> FromExternalError<Span<'a>, E> for SyntaxError<'a> {
+    fn from_external_error(input: Span<'a>, kind: ErrorKind, _: E) -> Self {
+        Self::from_error_kind(input, kind)
+    }
+}
+
 pub(crate) fn located<'a, O1, O2, E, F, G>(
     mut first: F,
     mut second: G,
@@ -50,6 +224,199 @@ where
     }
 }
 
-pub(crate) fn sp<'a, E: ParseError<Span<'a>>>(input: Span<'a>) -> IResult<Span<'a>, Span<'a>, E> {
-    take_while(|c| " \t\r\n".contains(c))(input)
+/// Like nom's `many_till`, but an `item` that fails is reported (merged with
+/// what `end` would have accepted there) and the input matched by `skip` is
+/// replaced with the error node built by `on_error`, so that parsing carries
+/// on with the next item.
+pub(crate) fn many_recover<'a, O1, O2, F, G, S>(
+    mut item: F,
+    mut end: G,
+    mut skip: S,
+    on_error: fn(Loc) -> O1,
+) -> impl FnMut(Span<'a>) -> PResult<'a, Vec<O1>>
+where
+    F: Parser<Span<'a>, O1, SyntaxError<'a>>,
+    G: Parser<Span<'a>, O2, SyntaxError<'a>>,
+    S: Parser<Span<'a>, Span<'a>, SyntaxError<'a>>,
+{
+    move |mut input: Span<'a>| {
+        let mut items = Vec::new();
+        loop {
+            let end_error = match end.parse(input) {
+                Ok((rest, _)) => return Ok((rest, items)),
+                Err(nom::Err::Error(e)) => e,
+                Err(e) => return Err(e),
+            };
+            match item.parse(input) {
+                Ok((rest, o)) => {
+                    items.push(o);
+                    input = rest;
+                }
+                Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => {
+                    input.extra.report(end_error.or(e).into_diagnostic());
+                    let (rest, error_node) =
+                        located(|i| skip.parse(i), |loc, _| on_error(loc))(input)?;
+                    items.push(error_node);
+                    input = rest;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Matches the closing delimiter `c`. Reaching the end of input or the start
+/// of the next statement (`def`, `use` or `pub`) instead is reported but accepted,
+/// so that an unclosed block keeps its children.
+pub(crate) fn close<'a>(c: char) -> impl FnMut(Span<'a>) -> PResult<'a, ()> {
+    move |input: Span<'a>| match char::<_, SyntaxError>(c)(input) {
+        Ok((input, _)) => Ok((input, ())),
+        Err(nom::Err::Error(e))
+            if input.fragment().is_empty() || starts_statement(input.fragment()) =>
+        {
+            input.extra.report(e.into_diagnostic());
+            Ok((input, ()))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn starts_statement(source: &str) -> bool {
+    ["def", "use", "pub"].iter().any(|keyword| {
+        source.starts_with(keyword)
+            && !source[keyword.len()..].starts_with(|c: char| c.is_alphanumeric() || c == '-')
+    })
+}
+
+/// Skips a broken child up to the end of its line, or up to the `}` closing
+/// the enclosing body, whichever comes first. Braces opened along the way are
+/// balanced and string literals are skipped whole.
+pub(crate) fn skip_child(input: Span) -> PResult<Span> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut in_comment = false;
+    let mut end = input.fragment().len();
+
+    for (i, c) in input.fragment().char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        if in_comment {
+            if c != '\n' {
+                continue;
+            }
+            in_comment = false;
+        }
+        match c {
+            '"' => in_string = true,
+            '#' => in_comment = true,
+            '{' => depth += 1,
+            '}' if depth == 0 => {
+                end = i;
+                break;
+            }
+            '}' => depth -= 1,
+            '\n' if depth == 0 => {
+                end = i;
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    take_at_least_one(input, end)
+}
+
+/// Skips a broken statement up to the next line starting a statement.
+pub(crate) fn skip_statement(input: Span) -> PResult<Span> {
+    let fragment = *input.fragment();
+    let mut end = fragment.len();
+    let mut offset = 0;
+
+    for line in fragment.split_inclusive('\n') {
+        if offset > 0 && starts_statement(line.trim_start()) {
+            end = offset;
+            break;
+        }
+        offset += line.len();
+    }
+
+    take_at_least_one(input, end)
+}
+
+fn take_at_least_one(input: Span, end: usize) -> PResult<Span> {
+    let end = if end == 0 {
+        input.fragment().chars().next().map_or(0, char::len_utf8)
+    } else {
+        end
+    };
+    Ok(nom::InputTake::take_split(&input, end))
+}
+
+fn line_comment(input: Span) -> PResult<Comment> {
+    located(preceded(char('#'), not_line_ending), |loc, text: Span| {
+        Comment {
+            loc,
+            kind: CommentKind::Line,
+            text: (*text.fragment()).to_owned(),
+        }
+    })(input)
+}
+
+fn block_comment(input: Span) -> PResult<Comment> {
+    located(
+        |input| {
+            let (input, opening) = tag("#|")(input)?;
+            alt((terminated_block, move |input| {
+                unterminated_block(input, opening)
+            }))(input)
+        },
+        |loc, text: Span| Comment {
+            loc,
+            kind: CommentKind::Block,
+            text: (*text.fragment()).to_owned(),
+        },
+    )(input)
+}
+
+fn terminated_block(input: Span) -> PResult<Span> {
+    let (input, text) = take_until("|#")(input)?;
+    let (input, _) = tag("|#")(input)?;
+    Ok((input, text))
+}
+
+/// A block comment running into the end of input is reported at its
+/// `opening` delimiter, but otherwise treated as if it was closed there.
+fn unterminated_block<'a>(input: Span<'a>, opening: Span<'a>) -> PResult<'a, Span<'a>> {
+    let (rest, text) = rest(input)?;
+    rest.extra.report(
+        Diagnostic::error(
+            codes::UNTERMINATED_COMMENT,
+            "block comment is never closed",
+            Loc {
+                start: opening.into(),
+                end: input.into(),
+            },
+        )
+        .with_expected(vec![String::from("`|#`")]),
+    );
+    Ok((rest, text))
+}
+
+fn comment(input: Span) -> PResult<()> {
+    let (rest, comment) = alt((block_comment, line_comment))(input)?;
+    input.extra.comment(comment);
+    Ok((rest, ()))
+}
+
+/// Skips whitespace and comments, which are recorded as trivia.
+pub(crate) fn sp(input: Span) -> PResult<Span> {
+    recognize(many0_count(alt((value((), multispace1), comment))))(input)
 }
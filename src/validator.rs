@@ -0,0 +1,156 @@
+//! Checks that elements are nested the way HTML allows, following the
+//! content model of each [`Tag`].
+//!
+//! Only the nesting written in a single body is checked: the body of a
+//! component and the children passed to one can end up anywhere, so they may
+//! hold any element at their top level.
+
+use crate::{
+    ast::*,
+    diagnostic::{codes, Diagnostic},
+    utils::Loc,
+};
+
+pub struct Validator<'a> {
+    modules: &'a [Module],
+    /// The module being validated.
+    module: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// The element a child is placed in.
+#[derive(Clone, Copy)]
+struct Parent<'a> {
+    tag: Option<&'a Tag>,
+    /// The content the parent accepts, with transparent elements taking the
+    /// content of their own parent.
+    content: Content,
+}
+
+impl<'a> Parent<'a> {
+    const ANY: Parent<'static> = Parent {
+        tag: None,
+        content: Content::Any,
+    };
+
+    fn allows(&self, child: &Tag) -> bool {
+        self.content.allows(child) || self.tag.is_some_and(|tag| tag.allows(child))
+    }
+}
+
+impl<'a> Validator<'a> {
+    pub fn validate(program: &'a Program) -> Vec<Diagnostic> {
+        let mut validator = Validator {
+            modules: &program.modules,
+            module: 0,
+            diagnostics: Vec::new(),
+        };
+        for (index, module) in program.modules.iter().enumerate() {
+            validator.module = index;
+            for statement in &module.statements {
+                match statement {
+                    Statement::Component(def) => validator.body(&def.body, Parent::ANY),
+                    Statement::Markup(child) => validator.child(child, Parent::ANY),
+                    Statement::Import(_) | Statement::Error(_) => {}
+                }
+            }
+        }
+        validator.diagnostics
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) {
+        let module = &self.modules[self.module];
+        self.diagnostics.push(diagnostic.in_module_of(module));
+    }
+
+    fn body(&mut self, body: &'a Body, parent: Parent<'a>) {
+        for child in &body.children {
+            self.child(child, parent);
+        }
+    }
+
+    fn child(&mut self, child: &'a Child, parent: Parent<'a>) {
+        match child {
+            Child::Element(element) => self.element(element, parent),
+            Child::Text(_) | Child::Expr(_) => self.text(child.loc(), parent),
+            Child::Component(component) => {
+                if let Some(body) = &component.body {
+                    self.body(body, Parent::ANY);
+                }
+            }
+            Child::Slot(slot) => {
+                if let Some(body) = &slot.body {
+                    self.body(body, parent);
+                }
+            }
+            Child::For(for_) => {
+                self.body(&for_.body, parent);
+                if let Some(else_body) = &for_.else_body {
+                    self.body(else_body, parent);
+                }
+            }
+            Child::If(if_) => {
+                for branch in &if_.branches {
+                    self.body(&branch.body, parent);
+                }
+                if let Some(else_body) = &if_.else_body {
+                    self.body(else_body, parent);
+                }
+            }
+            Child::Raw(_) | Child::Error(_) => {}
+        }
+    }
+
+    fn text(&mut self, loc: &Loc, parent: Parent<'a>) {
+        if let (Some(tag), Content::Metadata | Content::Specific) = (parent.tag, parent.content) {
+            self.report(Diagnostic::error(
+                codes::INVALID_CHILD,
+                format!("text cannot appear in `{}`", tag),
+                loc.clone(),
+            ));
+        }
+    }
+
+    fn element(&mut self, element: &'a Element, parent: Parent<'a>) {
+        let tag = &element.tag;
+        if let Some(parent_tag) = parent.tag {
+            if !parent.allows(tag) {
+                if tag.is_metadata() && !tag.is_flow() {
+                    self.report(Diagnostic::error(
+                        codes::MISPLACED_METADATA,
+                        format!("`{}` belongs in `head`, not in `{}`", tag, parent_tag),
+                        element.tag_loc(),
+                    ));
+                } else {
+                    self.report(Diagnostic::error(
+                        codes::INVALID_CHILD,
+                        format!("`{}` cannot be a child of `{}`", tag, parent_tag),
+                        element.tag_loc(),
+                    ));
+                }
+            }
+        }
+
+        let content = match tag.content() {
+            Content::Nothing => {
+                if let Some(child) = element.body.children.first() {
+                    self.report(Diagnostic::error(
+                        codes::VOID_CHILDREN,
+                        format!("`{}` is a void element and cannot have children", tag),
+                        child.loc().clone(),
+                    ));
+                }
+                return;
+            }
+            Content::Transparent => parent.content,
+            content => content,
+        };
+        self.body(
+            &element.body,
+            Parent {
+                tag: Some(tag),
+                content,
+            },
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::fmt;
+
+/// Data passed into a template, e.g. as the props of its entry component.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Bool(bool),
+    Number(f64),
+    List(Vec<Value>),
+    /// Read with dotted paths, as in `user.name`.
+    Map(HashMap<String, Value>),
+}
+
+/// Values for the parameters of the component being compiled, by name.
+pub type Props = HashMap<String, Value>;
+
+/// The data a template is rendered with. Names that are not a parameter or
+/// loop variable in scope are looked up here.
+pub trait Context {
+    fn get(&self, name: &str) -> Option<Value>;
+}
+
+impl Context for HashMap<String, Value> {
+    fn get(&self, name: &str) -> Option<Value> {
+        HashMap::get(self, name).cloned()
+    }
+}
+
+/// A map value is a context holding its entries; other values hold nothing.
+impl Context for Value {
+    fn get(&self, name: &str) -> Option<Value> {
+        match self {
+            Value::Map(entries) => entries.get(name).cloned(),
+            _ => None,
+        }
+    }
+}
+
+impl Value {
+    /// A short name for the kind of value, used in diagnostics.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+        }
+    }
+
+    /// Whether the value counts as true in a condition: `false`, `0`, `NaN`,
+    /// the empty string, the empty list and the empty map do not.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::String(value) => !value.is_empty(),
+            Value::Bool(value) => *value,
+            Value::Number(value) => *value != 0.0 && !value.is_nan(),
+            Value::List(items) => !items.is_empty(),
+            Value::Map(entries) => !entries.is_empty(),
+        }
+    }
+}
+
+/// The text a value renders as. Whole numbers have no fractional part, list
+/// items are separated by commas and map entries are listed by key.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(value) => write!(f, "{}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Number(value) => write!(f, "{}", value),
+            Value::List(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                Ok(())
+            }
+            Value::Map(entries) => {
+                let mut keys: Vec<&String> = entries.keys().collect();
+                keys.sort();
+                write!(f, "{{")?;
+                for (i, key) in keys.into_iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", key, entries[key])?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Number(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Number(value.into())
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(items: Vec<T>) -> Self {
+        Value::List(items.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<HashMap<String, Value>> for Value {
+    fn from(entries: HashMap<String, Value>) -> Self {
+        Value::Map(entries)
+    }
+}
@@ -28,10 +28,12 @@ fn it_parses_simple_module() {
     assert_eq!(
         program,
         Program {
-            modules: Module {
+            modules: vec![Module {
+                path: String::new(),
                 loc: gen_loc(1, 1, 3, 10),
                 statements: vec![Statement::Component(ComponentDef {
                     loc: gen_loc(1, 1, 3, 10),
+                    public: false,
                     id: Id {
                         loc: gen_loc(1, 5, 1, 9),
                         name: String::from("Main")
@@ -49,111 +51,1522 @@ fn it_parses_simple_module() {
                             },
                         })]
                     }
-                })]
-            }
+                })],
+                comments: Vec::new(),
+            }]
         }
     );
 
-    let compiled = Compiler::compile(program);
+    let compiled = Compiler::compile(program).unwrap();
 
     assert_eq!(compiled, "<!DOCTYPE html><html></html>");
 }
 
-// #[test]
-// fn it_parses_element_with_child_text_node() {
-//     assert_eq!(
-//         Parser::parse(r#"h1 "Page Title""#).unwrap(),
-//         Document {
-//             loc: gen_loc(1, 1, 16),
-//             content: Module {
-//                 loc: gen_loc(1, 1, 16),
-//                 body: Body {
-//                     loc: gen_loc(1, 1, 16),
-//                     children: vec![Child::Element(Element {
-//                         loc: gen_loc(1, 1, 16),
-//                         tag: Tag::H1,
-//                         attributes: None,
-//                         body: Some(Body {
-//                             loc: gen_loc(1, 4, 16),
-//                             children: vec![Child::Text(gen_loc(1, 4, 16), "Page Title".to_owned())]
-//                         }),
-//                     })]
-//                 }
-//             }
-//         }
-//     )
-// }
-
-// #[test]
-// fn it_parses_element_with_attributes() {
-//     assert_eq!(
-//         Parser::parse(r#"h1 [className="the-title"; data-heading="main"] "Page Title""#).unwrap(),
-//         Document {
-//             loc: gen_loc(1, 1, 61),
-//             content: Module {
-//                 loc: gen_loc(1, 1, 61),
-//                 body: Body {
-//                     loc: gen_loc(1, 1, 61),
-//                     children: vec![Child::Element(Element {
-//                         loc: gen_loc(1, 1, 61),
-//                         tag: Tag::H1,
-//                         attributes: Some(Attributes {
-//                             loc: gen_loc(1, 4, 48),
-//                             attr: vec![
-//                                 ("className".to_owned(), "the-title".to_owned()),
-//                                 ("data-heading".to_owned(), "main".to_owned())
-//                             ]
-//                             .into_iter()
-//                             .collect()
-//                         }),
-//                         body: Some(Body {
-//                             loc: gen_loc(1, 49, 61),
-//                             children: vec![Child::Text(
-//                                 gen_loc(1, 49, 61),
-//                                 "Page Title".to_owned()
-//                             )]
-//                         }),
-//                     })]
-//                 }
-//             }
-//         }
-//     )
-// }
-
-// #[test]
-// fn it_compiles_source() {
-//     let source = r#"
-//     html {
-//         head {
-//             title "Hyper!"
-//         }
-//     }
-//     "#;
-//     let document = Parser::parse(source).unwrap();
-//     let result = Compiler::compile(document);
-
-//     assert_eq!(
-//         result,
-//         "<!DOCTYPE html><html><head><title>Hyper!</title></head></html>".to_owned()
-//     )
-// }
-
-// #[test]
-// fn it_compiles_self_closing_tags() {
-//     let source = r#"
-//     html {
-//         head {
-//             link [rel="preload"; href="/some/asset.png"]
-//             title "Hyper!"
-//         }
-//     }
-//     "#;
-//     let document = Parser::parse(source).unwrap();
-//     let result = Compiler::compile(document);
-
-//     assert_eq!(
-//         result,
-//         r#"<!DOCTYPE html><html><head><link rel="preload" href="/some/asset.png"><title>Hyper!</title></head></html>"#
-//             .to_owned()
-//     )
-// }
+fn text(loc: Loc, value: &str) -> Child {
+    Child::Text(TextNode {
+        loc,
+        segments: vec![Segment::Text(value.to_owned())],
+    })
+}
+
+#[test]
+fn it_parses_element_with_child_text_node() {
+    assert_eq!(
+        Parser::parse(r#"h1 "Page Title""#).unwrap(),
+        Program {
+            modules: vec![Module {
+                path: String::new(),
+                loc: gen_loc(1, 1, 1, 16),
+                statements: vec![Statement::Markup(Child::Element(Element {
+                    loc: gen_loc(1, 1, 1, 16),
+                    tag: Tag::H1,
+                    attributes: None,
+                    body: Body {
+                        loc: gen_loc(1, 4, 1, 16),
+                        children: vec![text(gen_loc(1, 4, 1, 16), "Page Title")]
+                    },
+                }))],
+                comments: Vec::new(),
+            }]
+        }
+    )
+}
+
+#[test]
+fn it_parses_element_with_attributes() {
+    let attribute = |loc, name: (Loc, &str), value: (Loc, &str)| Attribute {
+        loc,
+        name: Id {
+            loc: name.0,
+            name: name.1.to_owned(),
+        },
+        value: Some(Expr {
+            loc: value.0,
+            kind: ExprKind::Str(vec![Segment::Text(value.1.to_owned())]),
+        }),
+    };
+
+    assert_eq!(
+        Parser::parse(r#"h1 [className="the-title"; data-heading="main"] "Page Title""#).unwrap(),
+        Program {
+            modules: vec![Module {
+                path: String::new(),
+                loc: gen_loc(1, 1, 1, 61),
+                statements: vec![Statement::Markup(Child::Element(Element {
+                    loc: gen_loc(1, 1, 1, 61),
+                    tag: Tag::H1,
+                    attributes: Some(Attributes {
+                        loc: gen_loc(1, 4, 1, 48),
+                        attr: vec![
+                            attribute(
+                                gen_loc(1, 5, 1, 26),
+                                (gen_loc(1, 5, 1, 14), "className"),
+                                (gen_loc(1, 15, 1, 26), "the-title"),
+                            ),
+                            attribute(
+                                gen_loc(1, 28, 1, 47),
+                                (gen_loc(1, 28, 1, 40), "data-heading"),
+                                (gen_loc(1, 41, 1, 47), "main"),
+                            ),
+                        ],
+                    }),
+                    body: Body {
+                        loc: gen_loc(1, 49, 1, 61),
+                        children: vec![text(gen_loc(1, 49, 1, 61), "Page Title")]
+                    },
+                }))],
+                comments: Vec::new(),
+            }]
+        }
+    )
+}
+
+#[test]
+fn it_compiles_source() {
+    let source = r#"
+    html {
+        head {
+            title "Hyper!"
+        }
+    }
+    "#;
+    let program = Parser::parse(source).unwrap();
+    let result = Compiler::compile(program).unwrap();
+
+    assert_eq!(
+        result,
+        "<!DOCTYPE html><html><head><title>Hyper!</title></head></html>"
+    )
+}
+
+#[test]
+fn it_compiles_self_closing_tags() {
+    let source = r#"
+    html {
+        head {
+            link [rel="preload"; href="/some/asset.png"]
+            title "Hyper!"
+        }
+    }
+    "#;
+    let program = Parser::parse(source).unwrap();
+    let result = Compiler::compile(program).unwrap();
+
+    assert_eq!(
+        result,
+        r#"<!DOCTYPE html><html><head><link rel="preload" href="/some/asset.png"><title>Hyper!</title></head></html>"#
+    )
+}
+
+#[test]
+fn it_reports_parse_errors_with_location() {
+    let source = "def Main = [] {\n    html {)\n    }\n}";
+    let error = Parser::parse(source).unwrap_err();
+
+    assert_eq!(
+        error.diagnostics,
+        vec![Diagnostic {
+            severity: Severity::Error,
+            code: codes::UNEXPECTED_TOKEN,
+            message: String::from(
+                "expected one of `}`, string, element, component name, found `)`"
+            ),
+            loc: gen_loc(2, 11, 2, 12),
+            expected: vec![
+                String::from("`}`"),
+                String::from("string"),
+                String::from("element"),
+                String::from("component name")
+            ],
+            module: None,
+        }]
+    );
+
+    assert_eq!(
+        format!("{}", error),
+        r#"error[E0001]: expected one of `}`, string, element, component name, found `)`
+ --> 2:11
+  |
+2 |     html {)
+  |           ^"#
+    );
+
+    let error = Parser::parse("def Main = [] {\n\t\thtml {)\n\t}\n}").unwrap_err();
+    assert_eq!(
+        format!("{}", error),
+        "error[E0001]: expected one of `}`, string, element, component name, found `)`
+ --> 2:9
+  |
+2 | \t\thtml {)
+  | \t\t      ^"
+    );
+}
+
+#[test]
+fn it_recovers_from_syntax_errors() {
+    let source = r#"def Main = [] {
+    div { ) }
+    html {}
+}
+def 1Broken = [] {}
+def Other = [] {
+    div {
+"#;
+    let (program, diagnostics) = Parser::parse_with_recovery(source);
+
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|d| (d.code, d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (codes::UNEXPECTED_TOKEN, gen_loc(2, 11, 2, 12)),
+            (codes::UNEXPECTED_TOKEN, gen_loc(5, 5, 5, 12)),
+            (codes::UNEXPECTED_EOF, gen_loc(8, 1, 8, 1)),
+        ]
+    );
+
+    let statements = &program.modules[0].statements;
+    assert_eq!(statements.len(), 3);
+    assert!(matches!(&statements[1], Statement::Error(_)));
+    match &statements[0] {
+        Statement::Component(main) => {
+            assert!(matches!(
+                &main.body.children[..],
+                [Child::Element(div), Child::Element(_)]
+                    if matches!(&div.body.children[..], [Child::Error(_)])
+            ));
+        }
+        statement => panic!("expected `Main`, got {:?}", statement),
+    }
+
+    assert_eq!(Parser::parse(source).unwrap_err().diagnostics, diagnostics);
+}
+
+#[test]
+fn it_keeps_comments_as_trivia() {
+    let source = r#"# index.hy
+def Main = [] { #| the
+    root |#
+    html {} # empty
+}"#;
+    let program = Parser::parse(source).unwrap();
+
+    assert_eq!(
+        program.modules[0].comments,
+        vec![
+            Comment {
+                loc: gen_loc(1, 1, 1, 11),
+                kind: CommentKind::Line,
+                text: String::from(" index.hy"),
+            },
+            Comment {
+                loc: gen_loc(2, 17, 3, 12),
+                kind: CommentKind::Block,
+                text: String::from(" the\n    root "),
+            },
+            Comment {
+                loc: gen_loc(4, 13, 4, 20),
+                kind: CommentKind::Line,
+                text: String::from(" empty"),
+            },
+        ]
+    );
+
+    assert_eq!(
+        Compiler::compile(program).unwrap(),
+        "<!DOCTYPE html><html></html>"
+    );
+}
+
+#[test]
+fn it_reports_unterminated_block_comments() {
+    let source = "def Main = [] {}\n#| never\nclosed";
+    let error = Parser::parse(source).unwrap_err();
+
+    assert_eq!(
+        error
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, d.message.as_str(), d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![(
+            codes::UNTERMINATED_COMMENT,
+            "block comment is never closed",
+            gen_loc(2, 1, 2, 3)
+        )]
+    );
+}
+
+#[test]
+fn it_parses_block_strings() {
+    let program = Parser::parse(
+        r#"def Main = [] {
+            div {
+                """Hyper is an "HTML DSL" focused on simplicity by reducing
+                the inherent verbosity that the language requires"""
+            }
+            pre {
+                """
+                  indented
+                back
+                """
+            }
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        Compiler::compile(program).unwrap(),
+        "<div>Hyper is an \"HTML DSL\" focused on simplicity by reducing\nthe inherent verbosity that the language requires</div><pre>  indented\nback</pre>"
+    );
+}
+
+#[test]
+fn it_inlines_single_children() {
+    let inline = Parser::parse(
+        r#"def Main = [] {
+            html head title "Hyper!"
+        }"#,
+    )
+    .unwrap();
+    let braces = Parser::parse(
+        r#"def Main = [] {
+            html {
+                head {
+                    title "Hyper!"
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        Compiler::compile(inline).unwrap(),
+        "<!DOCTYPE html><html><head><title>Hyper!</title></head></html>"
+    );
+    assert_eq!(
+        Compiler::compile(braces).unwrap(),
+        "<!DOCTYPE html><html><head><title>Hyper!</title></head></html>"
+    );
+}
+
+#[test]
+fn it_keeps_children_on_separate_lines_as_siblings() {
+    let program = Parser::parse(
+        r#"def Main = [] {
+            head {
+                link [rel="preload"]
+                title "Hyper!"
+            }
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        Compiler::compile(program).unwrap(),
+        r#"<head><link rel="preload"><title>Hyper!</title></head>"#
+    );
+}
+
+#[test]
+fn it_compiles_custom_elements() {
+    let program = Parser::parse(
+        r#"def Main = [] {
+            my-card [variant="primary"] {
+                sl-button "Save"
+            }
+        }"#,
+    )
+    .unwrap();
+
+    match &program.modules[0].statements[0] {
+        Statement::Component(main) => match &main.body.children[0] {
+            Child::Element(element) => {
+                assert_eq!(element.tag, Tag::Custom(String::from("my-card")))
+            }
+            child => panic!("expected a custom element, got {:?}", child),
+        },
+        statement => panic!("expected `Main`, got {:?}", statement),
+    }
+
+    assert_eq!(
+        Compiler::compile(program).unwrap(),
+        r#"<my-card variant="primary"><sl-button>Save</sl-button></my-card>"#
+    );
+}
+
+#[test]
+fn it_requires_component_names_to_be_capitalized() {
+    let error = Parser::parse(r#"def Main = [] { avatar [src="a.png"] }"#).unwrap_err();
+
+    assert_eq!(error.diagnostics.len(), 1);
+    assert_eq!(
+        error.diagnostics[0].message,
+        "expected one of `}`, string, element, component name, found `avatar`"
+    );
+}
+
+#[test]
+fn it_matches_whole_tag_names() {
+    let program = Parser::parse(
+        r#"def Main = [] {
+            header {
+                b "bold"
+                bdo "bdo"
+                section s "strike"
+                summary sub "sub"
+                p a "link"
+            }
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        Compiler::compile(program).unwrap(),
+        "<header><b>bold</b><bdo>bdo</bdo><section><s>strike</s></section><summary><sub>sub</sub></summary><p><a>link</a></p></header>"
+    );
+}
+
+#[test]
+fn it_matches_tag_names_case_sensitively() {
+    assert_eq!(Tag::from_name("header"), Some(Tag::Header));
+    assert_eq!(Tag::from_name("P"), None);
+    assert_eq!(Tag::from_name("Div"), None);
+}
+
+#[test]
+fn it_compiles_boolean_and_shorthand_attributes() {
+    let program = Parser::parse(
+        r#"def Main = [] {
+            form {
+                input [disabled]
+                Avatar [src="/me.png"]
+            }
+        }
+
+        def Avatar = [src] {
+            img [src]
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        Compiler::compile(program).unwrap(),
+        r#"<form><input disabled><img src="/me.png"></form>"#
+    );
+}
+
+#[test]
+fn it_binds_attribute_values_to_component_parameters() {
+    let program = Parser::parse(
+        r#"def Main = [] {
+            div {
+                Avatar [src="/me.png"]
+                Profile [photo="/you.png"]
+            }
+        }
+
+        def Avatar = [src] {
+            img [src=src]
+        }
+
+        def Profile = [photo] {
+            Avatar [src=photo]
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        Compiler::compile(program).unwrap(),
+        r#"<div><img src="/me.png"><img src="/you.png"></div>"#
+    );
+}
+
+#[test]
+fn it_keeps_attributes_in_source_order() {
+    let program = Parser::parse(
+        r#"def Main = [] {
+            link [rel="preload"; href="/some/asset.png"; as="image"]
+            div [class="card"; id="main"; class="card--wide"] {}
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        Compiler::compile(program).unwrap(),
+        r#"<link rel="preload" href="/some/asset.png" as="image"><div class="card card--wide" id="main"></div>"#
+    );
+
+    let program = Parser::parse(r#"def Main = [n] { div [class="a"; class=n] {} }"#).unwrap();
+    let mut props = Props::new();
+    props.insert(String::from("n"), Value::Number(3.0));
+    assert_eq!(
+        Compiler::compile_entry(program, "Main", props).unwrap(),
+        r#"<div class="a 3"></div>"#
+    );
+
+    let program = Parser::parse(r#"div [class; class="a"] {}"#).unwrap();
+    assert_eq!(
+        Compiler::compile(program)
+            .unwrap_err()
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, d.message.as_str(), d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![(
+            codes::TYPE_MISMATCH,
+            "cannot merge a boolean `class` with another one",
+            gen_loc(1, 13, 1, 18)
+        )]
+    );
+}
+
+#[test]
+fn it_reports_duplicate_attributes() {
+    let error =
+        Parser::parse(r#"def Main = [] { a [href="/"; href="/home"] "Home" }"#).unwrap_err();
+
+    assert_eq!(
+        error
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, d.message.as_str(), d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![(
+            codes::DUPLICATE_ATTRIBUTE,
+            "attribute `href` is given more than once",
+            gen_loc(1, 30, 1, 34)
+        )]
+    );
+}
+
+#[test]
+fn it_escapes_text_and_attributes() {
+    let program = Parser::parse(
+        r#"def Main = [] {
+            p [title="say \"hi\" & <wave>"] "<script>alert(1)</script> & more"
+            script "if (a < b && c) { x = '</script><b>'; }"
+            style "a::after { content: '</STYLE>'; }"
+            div raw "<b>trusted</b>"
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        Compiler::compile(program).unwrap(),
+        concat!(
+            r#"<p title="say &quot;hi&quot; &amp; &lt;wave&gt;">&lt;script&gt;alert(1)&lt;/script&gt; &amp; more</p>"#,
+            r#"<script>if (a < b && c) { x = '<\/script><b>'; }</script>"#,
+            r#"<style>a::after { content: '<\/STYLE>'; }</style>"#,
+            r#"<div><b>trusted</b></div>"#,
+        )
+    );
+
+    let program = Parser::parse(r#"def Main = [js] { script js script "{js}" }"#).unwrap();
+    let mut props = Props::new();
+    props.insert(String::from("js"), Value::from("a && b < c; '</script>'"));
+    assert_eq!(
+        Compiler::compile_entry(program, "Main", props).unwrap(),
+        concat!(
+            r#"<script>a && b < c; '<\/script>'</script>"#,
+            r#"<script>a && b < c; '<\/script>'</script>"#,
+        )
+    );
+
+    let program = Parser::parse(
+        r#"def Main = [xs] {
+            script {
+                for x in xs { "a<b;" x }
+            }
+        }"#,
+    )
+    .unwrap();
+    let mut props = Props::new();
+    props.insert(
+        String::from("xs"),
+        Value::List(vec![Value::from("c&&d;"), Value::from("'</script>'")]),
+    );
+    assert_eq!(
+        Compiler::compile_entry(program, "Main", props).unwrap(),
+        r#"<script>a<b;c&&d;a<b;'<\/script>'</script>"#
+    );
+
+    let program = Parser::parse(
+        r#"def Main = [] {
+            script { if true { "a < b" } else { "a > b" } }
+            style { if false { "a" } else { "a > b { color: red }" } }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        Compiler::compile(program).unwrap(),
+        "<script>a < b</script><style>a > b { color: red }</style>"
+    );
+
+    let program = Parser::parse(r#"script "const {a} = o; if (x) \{y}""#).unwrap();
+    assert_eq!(
+        Compiler::compile(program)
+            .unwrap_err()
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, d.message.as_str(), d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![(
+            codes::UNKNOWN_VARIABLE,
+            "cannot find `a` in this scope, write `\\{` for a literal brace in script",
+            gen_loc(1, 16, 1, 17)
+        )]
+    );
+}
+
+#[test]
+fn it_reports_component_expansion_errors() {
+    let program = Parser::parse(
+        r#"def Main = [] {
+            Missing
+            Avatar [size="2"]
+            Loop
+        }
+
+        def Avatar = [src] {
+            img [src]
+        }
+
+        def Loop = [] {
+            div Loop
+        }"#,
+    )
+    .unwrap();
+
+    let error = Compiler::compile(program).unwrap_err();
+    assert_eq!(
+        error
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, d.message.as_str(), d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                codes::UNKNOWN_COMPONENT,
+                "cannot find component `Missing`",
+                gen_loc(2, 13, 2, 20)
+            ),
+            (
+                codes::UNKNOWN_PARAMETER,
+                "component `Avatar` has no parameter `size`",
+                gen_loc(3, 21, 3, 25)
+            ),
+            (
+                codes::MISSING_PARAMETER,
+                "component `Avatar` requires parameter `src`",
+                gen_loc(3, 13, 3, 19)
+            ),
+            (
+                codes::RECURSIVE_COMPONENT,
+                "component `Loop` renders itself",
+                gen_loc(12, 17, 12, 21)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn it_compiles_the_selected_entry_component() {
+    let source = r#"def Main = [] {
+            Page [title="Home"]
+        }
+
+        def Page = [title] {
+            h1 [class=title] "Welcome"
+        }"#;
+
+    assert_eq!(
+        Compiler::compile(Parser::parse(source).unwrap()).unwrap(),
+        r#"<h1 class="Home">Welcome</h1>"#
+    );
+
+    let mut props = Props::new();
+    props.insert(String::from("title"), Value::from("About"));
+    assert_eq!(
+        Compiler::compile_entry(Parser::parse(source).unwrap(), "Page", props).unwrap(),
+        r#"<h1 class="About">Welcome</h1>"#
+    );
+
+    let error =
+        Compiler::compile_entry(Parser::parse(source).unwrap(), "Page", Props::new()).unwrap_err();
+    assert_eq!(error.diagnostics[0].code, codes::MISSING_PARAMETER);
+
+    let error = Compiler::compile_entry(Parser::parse(source).unwrap(), "Missing", Props::new())
+        .unwrap_err();
+    assert_eq!(error.diagnostics[0].code, codes::UNKNOWN_ENTRY);
+}
+
+#[test]
+fn it_renders_children_and_named_slots() {
+    let source = r#"def Main = [title] {
+            Page {
+                slot header { h1 [class=title] "Welcome" }
+                Card [heading="News"] { p "Hello" }
+                Card [heading="Empty"]
+                slot [name="native"] {}
+            }
+        }
+
+        def Page = [] {
+            header { slot header { h1 "Untitled" } }
+            main children
+        }
+
+        def Card = [heading] {
+            section {
+                h2 [title=heading] "Card"
+                children { p "Nothing yet" }
+            }
+        }"#;
+
+    let mut props = Props::new();
+    props.insert(String::from("title"), Value::from("Home"));
+    assert_eq!(
+        Compiler::compile_entry(Parser::parse(source).unwrap(), "Main", props).unwrap(),
+        concat!(
+            r#"<header><h1 class="Home">Welcome</h1></header>"#,
+            r#"<main><section><h2 title="News">Card</h2><p>Hello</p></section>"#,
+            r#"<section><h2 title="Empty">Card</h2><p>Nothing yet</p></section>"#,
+            r#"<slot name="native"></slot></main>"#,
+        )
+    );
+
+    let program = Parser::parse(
+        r#"def Main = [] {
+            Icon { slot label { "x" } }
+            Icon { "y" }
+        }
+
+        def Icon = [] {
+            i
+        }"#,
+    )
+    .unwrap();
+    let error = Compiler::compile(program).unwrap_err();
+    assert_eq!(
+        error
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, d.message.as_str(), d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                codes::UNKNOWN_SLOT,
+                "component `Icon` has no slot `label`",
+                gen_loc(2, 25, 2, 30)
+            ),
+            (
+                codes::UNEXPECTED_CHILDREN,
+                "component `Icon` does not render its children",
+                gen_loc(3, 13, 3, 17)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn it_renders_for_loops() {
+    let source = r#"def Main = [names] {
+            ol {
+                for name, i in names {
+                    li [value=i] name
+                } else {
+                    li "Nobody yet"
+                }
+            }
+        }"#;
+
+    let mut props = Props::new();
+    props.insert(String::from("names"), Value::from(vec!["Ada", "<Grace>"]));
+    assert_eq!(
+        Compiler::compile_entry(Parser::parse(source).unwrap(), "Main", props).unwrap(),
+        r#"<ol><li value="0">Ada</li><li value="1">&lt;Grace&gt;</li></ol>"#
+    );
+
+    let mut props = Props::new();
+    props.insert(String::from("names"), Value::List(Vec::new()));
+    assert_eq!(
+        Compiler::compile_entry(Parser::parse(source).unwrap(), "Main", props).unwrap(),
+        "<ol><li>Nobody yet</li></ol>"
+    );
+
+    let mut props = Props::new();
+    props.insert(String::from("names"), Value::from("Ada"));
+    let error = Compiler::compile_entry(Parser::parse(source).unwrap(), "Main", props).unwrap_err();
+    assert_eq!(
+        error
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, d.message.as_str(), d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![(
+            codes::NOT_ITERABLE,
+            "expected a list, found a string",
+            gen_loc(3, 32, 3, 37)
+        )]
+    );
+
+    let error = Compiler::compile(
+        Parser::parse(r#"def Main = [] { for item in items { li item } }"#).unwrap(),
+    )
+    .unwrap_err();
+    assert_eq!(error.diagnostics[0].code, codes::UNKNOWN_VARIABLE);
+    assert_eq!(error.diagnostics[0].loc, gen_loc(1, 29, 1, 34));
+}
+
+#[test]
+fn it_reads_bound_names_as_variables() {
+    let source = r#"def Card = [title; links] {
+            h1 title
+            ul {
+                for link in links { li link }
+            }
+            head title [lang="en"] "Docs"
+        }
+        code"#;
+
+    let mut props = Props::new();
+    props.insert(String::from("title"), Value::from("News"));
+    props.insert(String::from("links"), Value::from(vec!["a", "b"]));
+    assert_eq!(
+        Compiler::compile_entry(Parser::parse(source).unwrap(), "Card", props).unwrap(),
+        concat!(
+            "<h1>News</h1><ul><li>a</li><li>b</li></ul>",
+            r#"<head><title lang="en">Docs</title></head>"#
+        )
+    );
+
+    // Outside of the definition, `code` is not bound and is the element.
+    assert_eq!(
+        Compiler::compile(Parser::parse(source).unwrap()).unwrap(),
+        "<code></code>"
+    );
+    assert!(Checker::check(&Parser::parse(source).unwrap()).is_empty());
+}
+
+#[test]
+fn it_renders_conditionals() {
+    let source = r#"def Main = [count; muted; name] {
+            p {
+                if count == 0 {
+                    "No messages"
+                } else if count < 10 && !muted {
+                    "A few messages"
+                } else {
+                    "Lots of messages"
+                }
+            }
+            if name { b name }
+            if (muted || count >= 10) != true { i "quiet" }
+        }"#;
+
+    let render = |count: i32, muted: bool, name: &str| {
+        let mut props = Props::new();
+        props.insert(String::from("count"), Value::from(count));
+        props.insert(String::from("muted"), Value::from(muted));
+        props.insert(String::from("name"), Value::from(name));
+        Compiler::compile_entry(Parser::parse(source).unwrap(), "Main", props)
+    };
+
+    assert_eq!(
+        render(0, false, "").unwrap(),
+        "<p>No messages</p><i>quiet</i>"
+    );
+    assert_eq!(
+        render(3, false, "Ada").unwrap(),
+        "<p>A few messages</p><b>Ada</b><i>quiet</i>"
+    );
+    assert_eq!(render(3, true, "").unwrap(), "<p>Lots of messages</p>");
+
+    let error =
+        Compiler::compile(Parser::parse(r#"def Main = [] { if "a" < 1 { "?" } }"#).unwrap())
+            .unwrap_err();
+    assert_eq!(
+        error
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, d.message.as_str(), d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![(
+            codes::TYPE_MISMATCH,
+            "cannot compare a string with a number using `<`",
+            gen_loc(1, 20, 1, 27)
+        )]
+    );
+
+    // Render data may leave out the names a condition tests, but not the
+    // ones it compares.
+    let program = Parser::parse(
+        r#"p { if is_admin || !(muted && beta) { "Hi" } else { "Bye" } }
+        if count > 0 { "?" }"#,
+    )
+    .unwrap();
+    let renderer = Renderer::new(&program);
+    let mut data = Props::new();
+    data.insert(String::from("count"), Value::from(1));
+    assert_eq!(renderer.render(&data).unwrap(), "<p>Hi</p>?");
+    data.insert(String::from("muted"), Value::from(true));
+    data.insert(String::from("beta"), Value::from(true));
+    assert_eq!(renderer.render(&data).unwrap(), "<p>Bye</p>?");
+    assert_eq!(
+        renderer
+            .render(&Props::new())
+            .unwrap_err()
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, d.message.as_str(), d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![(
+            codes::UNKNOWN_VARIABLE,
+            "cannot find `count` in this scope",
+            gen_loc(2, 12, 2, 17)
+        )]
+    );
+}
+
+#[test]
+fn it_interpolates_expressions_in_strings() {
+    let source = r#"def Main = [id; name] {
+            a [href="/users/{id}"; title="{name}'s page"] "Hello, {name}!"
+            p "\{literal} {id}{ "
+            pre """
+                id: {id}
+                  {name}
+                """
+        }"#;
+
+    let program = Parser::parse(source).unwrap();
+    match &program.modules[0].statements[0] {
+        Statement::Component(main) => match &main.body.children[0] {
+            Child::Element(a) => assert_eq!(
+                a.body.children[0],
+                Child::Text(TextNode {
+                    loc: gen_loc(2, 59, 2, 75),
+                    segments: vec![
+                        Segment::Text(String::from("Hello, ")),
+                        Segment::Expr(Expr {
+                            loc: gen_loc(2, 68, 2, 72),
+                            kind: ExprKind::Var(String::from("name")),
+                        }),
+                        Segment::Text(String::from("!")),
+                    ],
+                })
+            ),
+            child => panic!("expected `a`, got {:?}", child),
+        },
+        statement => panic!("expected `Main`, got {:?}", statement),
+    }
+
+    let mut props = Props::new();
+    props.insert(String::from("id"), Value::from(7));
+    props.insert(String::from("name"), Value::from("<Ada>"));
+    assert_eq!(
+        Compiler::compile_entry(program, "Main", props).unwrap(),
+        concat!(
+            r#"<a href="/users/7" title="&lt;Ada&gt;'s page">Hello, &lt;Ada&gt;!</a>"#,
+            "<p>{literal} 7{ </p>",
+            "<pre>id: 7\n  &lt;Ada&gt;</pre>"
+        )
+    );
+
+    // A brace that does not open a closed expression is text, as in
+    // minified CSS and JSON.
+    let program = Parser::parse(
+        r#"style "a{color:red}.b{margin:0}"
+        script "var o = {\"a\": 1, \"b\": [2]};"
+        p "{name""#,
+    )
+    .unwrap();
+    assert_eq!(
+        Compiler::compile(program).unwrap(),
+        concat!(
+            "<style>a{color:red}.b{margin:0}</style>",
+            r#"<script>var o = {"a": 1, "b": [2]};</script>"#,
+            "<p>{name</p>"
+        )
+    );
+}
+
+#[test]
+fn it_renders_a_program_with_different_contexts() {
+    struct Site;
+
+    impl Context for Site {
+        fn get(&self, name: &str) -> Option<Value> {
+            match name {
+                "site" => Some(Value::from("example.com")),
+                "user" => {
+                    let mut user = Props::new();
+                    user.insert(String::from("name"), Value::from("Ada"));
+                    user.insert(String::from("admin"), Value::from(true));
+                    Some(Value::from(user))
+                }
+                _ => None,
+            }
+        }
+    }
+
+    let program = Parser::parse(
+        r#"def Main = [user] {
+            p "Signed in as {user.name}"
+            if user.admin { Footer }
+        }
+
+        def Footer = [] {
+            footer site
+        }"#,
+    )
+    .unwrap();
+    let renderer = Renderer::new(&program);
+
+    assert_eq!(
+        renderer.render(&Site).unwrap(),
+        "<p>Signed in as Ada</p><footer>example.com</footer>"
+    );
+
+    let mut data = Props::new();
+    data.insert(String::from("user"), Value::from("Grace"));
+    let error = renderer.render(&data).unwrap_err();
+    assert_eq!(
+        error
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, d.message.as_str(), d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                codes::TYPE_MISMATCH,
+                "cannot read `name` from a string, only from a map",
+                gen_loc(2, 35, 2, 39)
+            ),
+            (
+                codes::TYPE_MISMATCH,
+                "cannot read `admin` from a string, only from a map",
+                gen_loc(3, 21, 3, 26)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn it_loads_imported_modules() {
+    let loader = MemoryLoader::new()
+        .with_module(
+            "ui::avatar",
+            r#"pub def Avatar = [src] {
+    img [src; class="avatar"]
+}"#,
+        )
+        .with_module(
+            "ui::card",
+            r#"use ui::avatar::Avatar
+
+pub def Card = [photo] {
+    section { Avatar [src=photo] children }
+}"#,
+        );
+    let source = r#"use ui::card::Card
+use ui::avatar::Avatar
+
+def Main = [] {
+    Card [photo="/me.png"] { Avatar [src="/you.png"] }
+}"#;
+
+    let program = Parser::parse_program(source, &loader).unwrap();
+    assert_eq!(
+        program
+            .modules
+            .iter()
+            .map(|m| m.path.as_str())
+            .collect::<Vec<_>>(),
+        vec!["", "ui::card", "ui::avatar"]
+    );
+    assert_eq!(
+        Compiler::compile(program).unwrap(),
+        r#"<section><img src="/me.png" class="avatar"><img src="/you.png" class="avatar"></section>"#
+    );
+
+    let root = std::env::temp_dir().join(format!("hyper-loader-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("ui")).unwrap();
+    std::fs::write(
+        root.join("ui").join("avatar.hy"),
+        "pub def Avatar = [] { img }",
+    )
+    .unwrap();
+    let program = Parser::parse_program(
+        "use ui::avatar::Avatar\ndef Main = [] { Avatar }",
+        &FileLoader::new(&root),
+    );
+    std::fs::remove_dir_all(&root).unwrap();
+    assert_eq!(Compiler::compile(program.unwrap()).unwrap(), "<img>");
+}
+
+#[test]
+fn it_reports_unresolved_imports() {
+    let loader = MemoryLoader::new()
+        .with_module("a", "use b::B\npub def A = [] { B }")
+        .with_module("b", "use a::A\npub def B = [] { A }")
+        .with_module("c", "pub def C = [] {\n    div {)\n    }\n}");
+    let source = "use a::Nope\nuse missing::M\nuse c::C\ndef Main = [] {}";
+
+    let error = Parser::parse_program(source, &loader).unwrap_err();
+    assert_eq!(
+        error
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, d.location(), d.message.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                codes::IMPORT_CYCLE,
+                String::from("b:1:5"),
+                "modules import each other: a -> b -> a"
+            ),
+            (
+                codes::UNKNOWN_MODULE,
+                String::from("2:5"),
+                "cannot load module `missing`: no module `missing`"
+            ),
+            (
+                codes::UNEXPECTED_TOKEN,
+                String::from("c:2:10"),
+                "expected one of `}`, string, element, component name, found `)`"
+            ),
+            (
+                codes::UNKNOWN_IMPORT,
+                String::from("1:8"),
+                "module `a` has no component `Nope`"
+            ),
+        ]
+    );
+    assert_eq!(
+        error.to_string().split("\n\n").nth(2).unwrap(),
+        r#"error[E0001]: expected one of `}`, string, element, component name, found `)`
+ --> c:2:10
+  |
+2 |     div {)
+  |          ^"#
+    );
+}
+
+#[test]
+fn it_only_imports_public_components() {
+    let loader = MemoryLoader::new()
+        .with_module(
+            "ui",
+            r#"pub use ui::avatar::Avatar
+use ui::avatar::Badge
+
+def Helper = [] {}"#,
+        )
+        .with_module(
+            "ui::avatar",
+            r#"pub def Avatar = [] { img [class="avatar"] }
+pub def Badge = [] { span "new" }"#,
+        );
+
+    let program =
+        Parser::parse_program("use ui::Avatar\ndef Main = [] { Avatar }", &loader).unwrap();
+    assert_eq!(
+        Compiler::compile(program).unwrap(),
+        r#"<img class="avatar">"#
+    );
+
+    let error = Parser::parse_program("use ui::Helper\nuse ui::Badge\ndef Main = [] {}", &loader)
+        .unwrap_err();
+    assert_eq!(
+        error
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, d.message.as_str(), d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                codes::PRIVATE_COMPONENT,
+                "component `Helper` is private to module `ui`",
+                gen_loc(1, 9, 1, 15)
+            ),
+            (
+                codes::PRIVATE_COMPONENT,
+                "component `Badge` is private to module `ui`",
+                gen_loc(2, 9, 2, 14)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn it_renders_top_level_markup() {
+    let source = r#"html {
+    head {
+        link [rel="preload"; href="/some/asset.png"]
+        title "Hyper!"
+    }
+    body Greeting [name="Ada"]
+}
+
+def Greeting = [name] {
+    h1 "Hello, {name}"
+}
+
+def Main = [] {
+    p "Not the default"
+}"#;
+
+    assert_eq!(
+        Compiler::compile(Parser::parse(source).unwrap()).unwrap(),
+        r#"<!DOCTYPE html><html><head><link rel="preload" href="/some/asset.png"><title>Hyper!</title></head><body><h1>Hello, Ada</h1></body></html>"#
+    );
+    assert_eq!(
+        Compiler::compile_entry(Parser::parse(source).unwrap(), "Main", Props::new()).unwrap(),
+        "<p>Not the default</p>"
+    );
+
+    let program = Parser::parse("p \"{site}\"\nchildren { \"fallback\" }").unwrap();
+    let mut data = Props::new();
+    data.insert(String::from("site"), Value::from("example.com"));
+    assert_eq!(
+        Renderer::new(&program).render(&data).unwrap(),
+        "<p>example.com</p>fallback"
+    );
+}
+
+#[test]
+fn it_checks_names_and_parameters() {
+    let loader = MemoryLoader::new().with_module("ui", "pub def Badge = [] { span }");
+    let source = r#"use ui::Badge
+
+def Main = [items; title; unused] {
+    h1 title
+    for title in items {
+        for title, i in title { li "{i}" }
+    }
+    Card [heading="x"; size="2"]
+    Missing
+    Badge
+}
+
+def Card = [heading] {
+    h2 [title=heading] {}
+}
+
+def Badge = [] {}"#;
+
+    let program = Parser::parse_program(source, &loader).unwrap();
+    assert_eq!(
+        Checker::check(&program)
+            .iter()
+            .map(|d| (d.severity, d.code, d.message.as_str(), d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                Severity::Error,
+                codes::DUPLICATE_DEFINITION,
+                "`Badge` is defined more than once, first at 1:9",
+                gen_loc(17, 5, 17, 10)
+            ),
+            (
+                Severity::Warning,
+                codes::SHADOWED_VARIABLE,
+                "`title` shadows the variable declared at 3:20",
+                gen_loc(5, 9, 5, 14)
+            ),
+            (
+                Severity::Warning,
+                codes::SHADOWED_VARIABLE,
+                "`title` shadows the variable declared at 5:9",
+                gen_loc(6, 13, 6, 18)
+            ),
+            (
+                Severity::Error,
+                codes::UNKNOWN_PARAMETER,
+                "component `Card` has no parameter `size`",
+                gen_loc(8, 24, 8, 28)
+            ),
+            (
+                Severity::Error,
+                codes::UNKNOWN_COMPONENT,
+                "cannot find component `Missing`",
+                gen_loc(9, 5, 9, 12)
+            ),
+            (
+                Severity::Warning,
+                codes::UNUSED_PARAMETER,
+                "parameter `unused` of `Main` is never used",
+                gen_loc(3, 27, 3, 33)
+            ),
+        ]
+    );
+
+    let source = r#"def Card = [] {
+    section { slot title { h2 "Untitled" } }
+}
+
+Card {
+    slot nope { p "Hello" }
+    p "World"
+}"#;
+    assert_eq!(
+        Checker::check(&Parser::parse(source).unwrap())
+            .iter()
+            .map(|d| (d.code, d.message.as_str(), d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                codes::UNKNOWN_SLOT,
+                "component `Card` has no slot `nope`",
+                gen_loc(6, 10, 6, 14)
+            ),
+            (
+                codes::UNEXPECTED_CHILDREN,
+                "component `Card` does not render its children",
+                gen_loc(5, 1, 5, 5)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn it_validates_element_nesting() {
+    let source = r#"html {
+    head {
+        title "Docs"
+        div {}
+    }
+    body {
+        img [src="a.png"] { "caption" }
+        div { li "one" }
+        p { a { div "block" } }
+        table { tr { td "cell" } }
+        ul { "text" li { meta } }
+    }
+}"#;
+
+    let program = Parser::parse(source).unwrap();
+    assert_eq!(
+        Validator::validate(&program)
+            .iter()
+            .map(|d| (d.code, d.message.as_str(), d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                codes::INVALID_CHILD,
+                "`div` cannot be a child of `head`",
+                gen_loc(4, 9, 4, 12)
+            ),
+            (
+                codes::VOID_CHILDREN,
+                "`img` is a void element and cannot have children",
+                gen_loc(7, 29, 7, 38)
+            ),
+            (
+                codes::INVALID_CHILD,
+                "`li` cannot be a child of `div`",
+                gen_loc(8, 15, 8, 17)
+            ),
+            (
+                codes::INVALID_CHILD,
+                "`div` cannot be a child of `a`",
+                gen_loc(9, 17, 9, 20)
+            ),
+            (
+                codes::INVALID_CHILD,
+                "text cannot appear in `ul`",
+                gen_loc(11, 14, 11, 20)
+            ),
+            (
+                codes::MISPLACED_METADATA,
+                "`meta` belongs in `head`, not in `li`",
+                gen_loc(11, 26, 11, 30)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn it_lints_accessibility() {
+    let source = r#"html {
+    body {
+        h1 "Title"
+        h3 "Skipped"
+        img [src="a.png"]
+        img [src="b.png"; alt=""]
+        label { "Name" input [name="name"] }
+        label [for="email"] "Email"
+        input [id="email"]
+        input [id="phone"]
+        input [type="hidden"; name="token"]
+        textarea [aria-label="Message"] {}
+        a [href="/"] { img [src="logo.png"; alt=""] }
+        a [href="/home"] { img [src="logo.png"; alt="Home"] }
+    }
+}"#;
+
+    let program = Parser::parse(source).unwrap();
+    assert_eq!(
+        Linter::new()
+            .lint(&program)
+            .iter()
+            .map(|d| (d.code, d.message.as_str(), d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                codes::MISSING_LANG,
+                "`html` has no `lang` attribute",
+                gen_loc(1, 1, 1, 5)
+            ),
+            (
+                codes::SKIPPED_HEADING_LEVEL,
+                "`h3` follows `h1`, skipping a heading level",
+                gen_loc(4, 9, 4, 11)
+            ),
+            (
+                codes::MISSING_ALT,
+                "`img` has no `alt` text",
+                gen_loc(5, 9, 5, 12)
+            ),
+            (
+                codes::UNLABELLED_CONTROL,
+                "`input` has no label",
+                gen_loc(10, 9, 10, 14)
+            ),
+            (
+                codes::EMPTY_LINK,
+                "link has no text",
+                gen_loc(13, 9, 13, 10)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn it_runs_lints_at_configured_levels() {
+    struct NoMarquee;
+
+    impl Lint for NoMarquee {
+        fn name(&self) -> &'static str {
+            "no-marquee"
+        }
+
+        fn check_element(&self, element: &Element, cx: &mut LintContext) {
+            if element.tag == Tag::Custom(String::from("x-marquee")) {
+                cx.report(element.tag_loc(), "scrolling text is hard to read");
+            }
+        }
+    }
+
+    let source = r#"#![deny(img-alt)]
+div {
+    img [src="a.png"]
+    #[allow(img-alt, link-text)]
+    p { img [src="b.png"] a [href="/"] {} }
+    #[warn(imgalt)]
+    x-marquee "Hello"
+    input [name="q"]
+}"#;
+
+    let linter = Linter::new()
+        .with_lint(NoMarquee)
+        .with_config("# house rules\ncontrol-label = allow\n\nno-marquee = deny\n")
+        .unwrap();
+    let program = Parser::parse(source).unwrap();
+    let diagnostics = linter.lint(&program);
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|d| (d.severity, d.code, d.message.as_str(), d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                Severity::Error,
+                codes::MISSING_ALT,
+                "`img` has no `alt` text",
+                gen_loc(3, 5, 3, 8)
+            ),
+            (
+                Severity::Warning,
+                codes::UNKNOWN_LINT,
+                "unknown lint `imgalt`",
+                gen_loc(6, 5, 6, 20)
+            ),
+            (
+                Severity::Error,
+                "no-marquee",
+                "scrolling text is hard to read",
+                gen_loc(7, 5, 7, 14)
+            ),
+        ]
+    );
+
+    assert_eq!(
+        to_json(&diagnostics[..1]),
+        r#"[{"severity":"error","code":"W0003","message":"`img` has no `alt` text","module":null,"start":{"line":3,"column":5},"end":{"line":3,"column":8},"expected":[]}]"#
+    );
+
+    assert_eq!(
+        Linter::new()
+            .with_config("img-alt = deny\nheading-order = sometimes")
+            .err()
+            .map(|e| e.to_string()),
+        Some(String::from(
+            "line 2: unknown level `sometimes`, expected `allow`, `warn` or `deny`"
+        ))
+    );
+    assert_eq!(
+        Linter::new()
+            .with_config("no-marquee = deny")
+            .err()
+            .map(|e| e.to_string()),
+        Some(String::from("line 1: unknown lint `no-marquee`"))
+    );
+}
+
+#[test]
+fn it_allows_lints_for_the_first_definition() {
+    let source = r#"#[allow(img-alt)]
+def Spacer = [] {
+    img [src="a.gif"]
+    img [src="b.gif"]
+}
+
+def Main = [] {
+    Spacer
+    img [src="c.gif"]
+}"#;
+
+    let program = Parser::parse(source).unwrap();
+    let diagnostics = Linter::new().lint(&program);
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|d| (d.code, d.loc.clone()))
+            .collect::<Vec<_>>(),
+        vec![(codes::MISSING_ALT, gen_loc(9, 5, 9, 8))]
+    );
+}